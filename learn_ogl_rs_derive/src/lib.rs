@@ -0,0 +1,277 @@
+//! Derive macros for `learn_ogl_rs`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, punctuated::Punctuated, spanned::Spanned, Data, DeriveInput, Error, Expr,
+    Fields, FieldsNamed, Ident, LitStr, Path, Token,
+};
+
+/// Binds a struct to a GLSL struct uniform, see `learn_ogl_rs::shader::ShaderUniforms`.
+///
+/// Generates a `<Name>Uniforms` struct holding a `Uniform` handle per field, which can be
+/// renamed with `#[uniform(handles = OtherName)]` on the struct. Fields accept:
+/// - `#[uniform(rename = "glslName")]` to use a different name in GLSL.
+/// - `#[uniform(with = path::to::fn)]` to transform the value before uploading it, the function
+///   takes and returns the field's type.
+/// - `#[uniform(skip)]` for fields that aren't uploaded.
+#[proc_macro_derive(ShaderUniforms, attributes(uniform))]
+pub fn derive_shader_uniforms(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_shader_uniforms(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Checks at compile time that a `#[repr(C)]` struct is laid out like std140 lays out the GLSL
+/// struct, see `learn_ogl_rs::shader::Std140`. Padding has to be spelled out as fields of its
+/// own marked `#[uniform(skip)]`, which are left out of the checks. Fields named differently
+/// in GLSL take `#[uniform(rename = "glslName")]`.
+#[proc_macro_derive(Std140, attributes(uniform))]
+pub fn derive_std140(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_std140(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Binds a `Std140` struct to a whole uniform block, see `learn_ogl_rs::shader::BufferBlock`.
+///
+/// Takes `#[block(binding = BINDING)]` on the struct, and optionally `name = "BlockName"` when
+/// the block isn't named like the struct. The members come from the `Std140` derive.
+#[proc_macro_derive(BufferBlock, attributes(block, uniform))]
+pub fn derive_buffer_block(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_buffer_block(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+struct Field {
+    ident: Ident,
+    ty: syn::Type,
+    vis: syn::Visibility,
+    glsl_name: String,
+    with: Option<Path>,
+}
+
+/// The named fields of a non-generic struct.
+fn named_fields<'a>(input: &'a DeriveInput, derive: &str) -> Result<&'a FieldsNamed, Error> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            format!("{derive} can't be derived for generic structs"),
+        ));
+    }
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new(
+            input.ident.span(),
+            format!("{derive} can only be derived for structs"),
+        ));
+    };
+    match &data.fields {
+        Fields::Named(named) => Ok(named),
+        fields => Err(Error::new(
+            fields.span(),
+            format!("{derive} needs a struct with named fields"),
+        )),
+    }
+}
+
+/// Fields along with their `#[uniform(...)]` attributes, leaving out skipped ones.
+fn uniform_fields(fields: &FieldsNamed) -> Result<Vec<Field>, Error> {
+    let mut uniform_fields = vec![];
+    'fields: for field in &fields.named {
+        let ident = field.ident.clone().unwrap();
+        let mut glsl_name = ident.to_string();
+        let mut with = None;
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("uniform"))
+        {
+            let mut skip = false;
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    skip = true;
+                } else if meta.path.is_ident("rename") {
+                    glsl_name = meta.value()?.parse::<LitStr>()?.value();
+                } else if meta.path.is_ident("with") {
+                    with = Some(meta.value()?.parse::<Path>()?);
+                } else {
+                    return Err(meta.error("expected `skip`, `rename = \"...\"` or `with = fn`"));
+                }
+                Ok(())
+            })?;
+            if skip {
+                continue 'fields;
+            }
+        }
+        uniform_fields.push(Field {
+            ident,
+            ty: field.ty.clone(),
+            vis: field.vis.clone(),
+            glsl_name,
+            with,
+        });
+    }
+    Ok(uniform_fields)
+}
+
+fn expand_shader_uniforms(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = uniform_fields(named_fields(&input, "ShaderUniforms")?)?;
+
+    let mut handles = format_ident!("{}Uniforms", input.ident);
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("uniform"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("handles") {
+                handles = meta.value()?.parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("expected `handles = Name`"))
+            }
+        })?;
+    }
+
+    let krate = quote!(::learn_ogl_rs::shader);
+    let name = &input.ident;
+    let vis = &input.vis;
+    let handle_fields = fields
+        .iter()
+        .map(|Field { ident, ty, vis, .. }| quote!(#vis #ident: #krate::Uniform<#ty>));
+    let glsl_names: Vec<_> = fields.iter().map(|field| &field.glsl_name).collect();
+    let idents: Vec<_> = fields.iter().map(|field| &field.ident).collect();
+    let values = fields.iter().map(|Field { ident, with, .. }| match with {
+        Some(with) => quote!(#with(self.#ident)),
+        None => quote!(self.#ident),
+    });
+    let doc = format!("Handles to the uniforms of a `{name}`, see `ShaderUniforms`.");
+    let value_exprs: Vec<Expr> = values.map(syn::parse2).collect::<Result<_, _>>()?;
+
+    Ok(quote! {
+        #[doc = #doc]
+        #vis struct #handles {
+            #(#handle_fields,)*
+        }
+
+        impl #krate::ShaderUniforms for #name {
+            type Uniforms = #handles;
+
+            const UNIFORM_NAMES: &'static [&'static str] = &[#(#glsl_names),*];
+
+            fn uniforms(
+                gl: &::learn_ogl_rs::gl::Gl,
+                shader: &#krate::Shader,
+                prefix: &str,
+            ) -> ::std::result::Result<Self::Uniforms, #krate::UniformError> {
+                shader.check_uniform_struct(prefix, Self::UNIFORM_NAMES)?;
+                Ok(#handles {
+                    #(#idents: #krate::Uniform::new(
+                        gl,
+                        shader,
+                        &::std::format!("{}.{}", prefix, #glsl_names),
+                    )?,)*
+                })
+            }
+
+            fn upload(&self, uniforms: &Self::Uniforms) {
+                #(#krate::UniformGetSet::set(&uniforms.#idents, #value_exprs);)*
+            }
+        }
+    })
+}
+
+fn expand_std140(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = uniform_fields(named_fields(&input, "Std140")?)?;
+
+    let mut repr_c = false;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("repr"))
+    {
+        let reprs = attr.parse_args_with(Punctuated::<Ident, Token![,]>::parse_terminated)?;
+        repr_c |= reprs.iter().any(|repr| repr == "C");
+    }
+    if !repr_c {
+        return Err(Error::new(
+            input.ident.span(),
+            "Std140 needs a #[repr(C)] struct",
+        ));
+    }
+
+    let krate = quote!(::learn_ogl_rs::shader);
+    let name = &input.ident;
+    let field_checks = fields.iter().map(|Field { ident, ty, .. }| {
+        let message = format!("`{name}::{ident}` isn't aligned like std140 aligns it");
+        quote! {
+            assert!(
+                ::std::mem::offset_of!(#name, #ident) % <#ty as #krate::Std140>::ALIGN == 0,
+                #message
+            );
+        }
+    });
+    let size_message = format!("`{name}` has to be padded to a multiple of 16 bytes");
+    let members = fields.iter().map(
+        |Field {
+             ident, glsl_name, ..
+         }| { quote!((#glsl_name, ::std::mem::offset_of!(#name, #ident))) },
+    );
+
+    // Structs are 16 byte aligned in std140 and their size rounded up to match.
+    Ok(quote! {
+        unsafe impl #krate::Std140 for #name {
+            const ALIGN: usize = 16;
+            const MEMBERS: &'static [(&'static str, usize)] = &[#(#members),*];
+        }
+
+        const _: () = {
+            #(#field_checks)*
+            assert!(::std::mem::size_of::<#name>() % 16 == 0, #size_message);
+        };
+    })
+}
+
+fn expand_buffer_block(input: DeriveInput) -> Result<TokenStream2, Error> {
+    named_fields(&input, "BufferBlock")?;
+
+    let mut block_name = input.ident.to_string();
+    let mut binding = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("block"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                block_name = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("binding") {
+                binding = Some(meta.value()?.parse::<Expr>()?);
+            } else {
+                return Err(meta.error("expected `name = \"...\"` or `binding = ...`"));
+            }
+            Ok(())
+        })?;
+    }
+    let Some(binding) = binding else {
+        return Err(Error::new(
+            input.ident.span(),
+            "BufferBlock needs a #[block(binding = ...)] attribute",
+        ));
+    };
+
+    let krate = quote!(::learn_ogl_rs::shader);
+    let name = &input.ident;
+
+    Ok(quote! {
+        impl #krate::BufferBlock for #name {
+            const NAME: &'static str = #block_name;
+            const BINDING: ::learn_ogl_rs::gl::types::GLuint = #binding;
+        }
+    })
+}
@@ -1,17 +1,130 @@
-#![allow(clippy::all)]
-// I had to do this because of u128s, apparently its not really a problem anymore though?
-#![allow(improper_ctypes)]
-#![allow(non_camel_case_types)]
-#![allow(non_upper_case_globals)]
-#![allow(non_snake_case)]
-
-include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
-
-/// Imports file and panics if it can't be found/imported.
-pub fn import_file(path: &str) -> &aiScene {
-    unsafe {
-        aiImportFile(format!("{path}\0").as_ptr() as *const i8, 0)
-            .as_ref()
-            .expect(&format!("File at '{path}' not found or cannot be imported. Please try again with another path."))
+mod material;
+mod mesh;
+mod scene;
+mod texture;
+
+use std::{ffi::CStr, fmt, path::PathBuf};
+
+pub use self::material::{Material, TextureType};
+pub use self::mesh::Mesh;
+pub use self::scene::{Node, Scene};
+pub use self::texture::{Texel, Texture, TextureData};
+
+mod sys {
+    #![allow(clippy::all)]
+    // I had to do this because of u128s, apparently its not really a problem anymore though?
+    #![allow(improper_ctypes)]
+    #![allow(non_camel_case_types)]
+    #![allow(non_upper_case_globals)]
+    #![allow(non_snake_case)]
+    #![allow(dead_code)]
+
+    include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+}
+
+#[derive(Debug)]
+pub struct ImportError {
+    path: PathBuf,
+    message: String,
+}
+
+impl ImportError {
+    fn from_assimp(path: PathBuf) -> Self {
+        let message = unsafe {
+            let message = sys::aiGetErrorString();
+            if message.is_null() {
+                String::new()
+            } else {
+                CStr::from_ptr(message).to_string_lossy().into_owned()
+            }
+        };
+        Self { path, message }
+    }
+
+    pub(crate) fn new(path: PathBuf, message: impl Into<String>) -> Self {
+        Self {
+            path,
+            message: message.into(),
+        }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Failed to import '{}': {}",
+            self.path.display(),
+            self.message
+        )
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// Post processing steps assimp runs on a scene while importing it.
+/// ```
+/// # use learn_ogl_rs::assimp::PostProcess;
+/// let flags = PostProcess::new().triangulate().gen_normals();
+/// assert_ne!(flags, PostProcess::new());
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PostProcess(u32);
+
+impl PostProcess {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Splits polygons with more than three indices into triangles.
+    pub fn triangulate(self) -> Self {
+        self.with(sys::aiPostProcessSteps_aiProcess_Triangulate)
+    }
+    /// Generates smooth normals for meshes that don't have any.
+    pub fn gen_normals(self) -> Self {
+        self.with(sys::aiPostProcessSteps_aiProcess_GenSmoothNormals)
+    }
+    /// Flips texture coordinates along the y axis.
+    pub fn flip_uvs(self) -> Self {
+        self.with(sys::aiPostProcessSteps_aiProcess_FlipUVs)
+    }
+    /// Calculates tangents and bitangents for meshes with normals and texture coordinates.
+    pub fn calc_tangents(self) -> Self {
+        self.with(sys::aiPostProcessSteps_aiProcess_CalcTangentSpace)
+    }
+    /// Merges identical vertices so meshes can be drawn indexed.
+    pub fn join_vertices(self) -> Self {
+        self.with(sys::aiPostProcessSteps_aiProcess_JoinIdenticalVertices)
+    }
+
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    fn with(self, step: sys::aiPostProcessSteps) -> Self {
+        Self(self.0 | step)
+    }
+}
+
+/// Converts an `aiString` into an owned string, replacing invalid UTF-8.
+fn ai_string_to_string(string: &sys::aiString) -> String {
+    let len = (string.length as usize).min(string.data.len());
+    let bytes: Vec<u8> = string.data[..len].iter().map(|c| *c as u8).collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Assimp leaves array pointers null when the count is zero.
+unsafe fn raw_slice<'a, T>(ptr: *const T, len: u32) -> &'a [T] {
+    if ptr.is_null() || len == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(ptr, len as usize)
     }
 }
@@ -0,0 +1,130 @@
+use std::{mem::zeroed, ptr::null_mut};
+
+use glam::{vec4, Vec4};
+
+use super::{ai_string_to_string, sys};
+
+const MATKEY_NAME: &[u8] = b"?mat.name\0";
+const MATKEY_SHININESS: &[u8] = b"$mat.shininess\0";
+const MATKEY_COLOR_AMBIENT: &[u8] = b"$clr.ambient\0";
+const MATKEY_COLOR_DIFFUSE: &[u8] = b"$clr.diffuse\0";
+const MATKEY_COLOR_SPECULAR: &[u8] = b"$clr.specular\0";
+const MATKEY_COLOR_EMISSIVE: &[u8] = b"$clr.emissive\0";
+
+/// The purpose a material's texture is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureType {
+    Diffuse,
+    Specular,
+    Ambient,
+    Emissive,
+    /// Bump maps, e.g. `map_Bump` in `.mtl` files.
+    Height,
+    Normals,
+    Shininess,
+    Opacity,
+    AmbientOcclusion,
+}
+
+impl TextureType {
+    fn raw(self) -> sys::aiTextureType {
+        match self {
+            TextureType::Diffuse => sys::aiTextureType_aiTextureType_DIFFUSE,
+            TextureType::Specular => sys::aiTextureType_aiTextureType_SPECULAR,
+            TextureType::Ambient => sys::aiTextureType_aiTextureType_AMBIENT,
+            TextureType::Emissive => sys::aiTextureType_aiTextureType_EMISSIVE,
+            TextureType::Height => sys::aiTextureType_aiTextureType_HEIGHT,
+            TextureType::Normals => sys::aiTextureType_aiTextureType_NORMALS,
+            TextureType::Shininess => sys::aiTextureType_aiTextureType_SHININESS,
+            TextureType::Opacity => sys::aiTextureType_aiTextureType_OPACITY,
+            TextureType::AmbientOcclusion => sys::aiTextureType_aiTextureType_AMBIENT_OCCLUSION,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Material<'a> {
+    raw: &'a sys::aiMaterial,
+}
+
+impl<'a> Material<'a> {
+    pub(super) fn new(raw: &'a sys::aiMaterial) -> Self {
+        Self { raw }
+    }
+
+    pub fn name(&self) -> Option<String> {
+        unsafe {
+            let mut name: sys::aiString = zeroed();
+            let result =
+                sys::aiGetMaterialString(self.raw, MATKEY_NAME.as_ptr().cast(), 0, 0, &mut name);
+            (result == sys::aiReturn_aiReturn_SUCCESS).then(|| ai_string_to_string(&name))
+        }
+    }
+
+    pub fn texture_count(&self, texture_type: TextureType) -> usize {
+        unsafe { sys::aiGetMaterialTextureCount(self.raw, texture_type.raw()) as usize }
+    }
+
+    /// Path of the texture exactly as written in the model file. Embedded textures are
+    /// referenced as `*<index>`, see `Scene::embedded_texture`.
+    pub fn texture_path(&self, texture_type: TextureType, index: usize) -> Option<String> {
+        unsafe {
+            let mut path: sys::aiString = zeroed();
+            let result = sys::aiGetMaterialTexture(
+                self.raw,
+                texture_type.raw(),
+                index as u32,
+                &mut path,
+                null_mut(),
+                null_mut(),
+                null_mut(),
+                null_mut(),
+                null_mut(),
+                null_mut(),
+            );
+            (result == sys::aiReturn_aiReturn_SUCCESS).then(|| ai_string_to_string(&path))
+        }
+    }
+
+    /// Specular exponent, `Ns` in `.mtl` files.
+    pub fn shininess(&self) -> Option<f32> {
+        self.float(MATKEY_SHININESS)
+    }
+
+    pub fn ambient_color(&self) -> Option<Vec4> {
+        self.color(MATKEY_COLOR_AMBIENT)
+    }
+    pub fn diffuse_color(&self) -> Option<Vec4> {
+        self.color(MATKEY_COLOR_DIFFUSE)
+    }
+    pub fn specular_color(&self) -> Option<Vec4> {
+        self.color(MATKEY_COLOR_SPECULAR)
+    }
+    pub fn emissive_color(&self) -> Option<Vec4> {
+        self.color(MATKEY_COLOR_EMISSIVE)
+    }
+
+    fn float(&self, key: &[u8]) -> Option<f32> {
+        unsafe {
+            let mut value = 0.0;
+            let result = sys::aiGetMaterialFloatArray(
+                self.raw,
+                key.as_ptr().cast(),
+                0,
+                0,
+                &mut value,
+                null_mut(),
+            );
+            (result == sys::aiReturn_aiReturn_SUCCESS).then_some(value)
+        }
+    }
+
+    fn color(&self, key: &[u8]) -> Option<Vec4> {
+        unsafe {
+            let mut color: sys::aiColor4D = zeroed();
+            let result = sys::aiGetMaterialColor(self.raw, key.as_ptr().cast(), 0, 0, &mut color);
+            (result == sys::aiReturn_aiReturn_SUCCESS)
+                .then(|| vec4(color.r, color.g, color.b, color.a))
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use glam::{vec2, vec3, Vec2, Vec3};
+
+use super::{ai_string_to_string, raw_slice, sys};
+
+#[derive(Clone, Copy)]
+pub struct Mesh<'a> {
+    raw: &'a sys::aiMesh,
+}
+
+impl<'a> Mesh<'a> {
+    pub(super) fn new(raw: &'a sys::aiMesh) -> Self {
+        Self { raw }
+    }
+
+    pub fn name(&self) -> String {
+        ai_string_to_string(&self.raw.mName)
+    }
+
+    pub fn num_vertices(&self) -> usize {
+        self.raw.mNumVertices as usize
+    }
+
+    pub fn positions(&self) -> impl ExactSizeIterator<Item = Vec3> + 'a {
+        self.vectors(self.raw.mVertices)
+    }
+
+    /// `None` if the mesh has no normals, see `PostProcess::gen_normals`.
+    pub fn normals(&self) -> Option<impl ExactSizeIterator<Item = Vec3> + 'a> {
+        (!self.raw.mNormals.is_null()).then(|| self.vectors(self.raw.mNormals))
+    }
+
+    /// `None` if the mesh has no tangents, see `PostProcess::calc_tangents`.
+    pub fn tangents(&self) -> Option<impl ExactSizeIterator<Item = Vec3> + 'a> {
+        (!self.raw.mTangents.is_null()).then(|| self.vectors(self.raw.mTangents))
+    }
+
+    /// Two dimensional texture coordinates of the given UV channel.
+    pub fn tex_coords(&self, channel: usize) -> Option<impl ExactSizeIterator<Item = Vec2> + 'a> {
+        let coords = *self.raw.mTextureCoords.get(channel)?;
+        (!coords.is_null()).then(|| self.vectors(coords).map(|coord| vec2(coord.x, coord.y)))
+    }
+
+    /// Indices of each face. Unless the scene was triangulated these can be points, lines or
+    /// polygons.
+    pub fn faces(&self) -> impl ExactSizeIterator<Item = &'a [u32]> {
+        unsafe { raw_slice(self.raw.mFaces, self.raw.mNumFaces) }
+            .iter()
+            .map(|face| unsafe { raw_slice(face.mIndices, face.mNumIndices) })
+    }
+
+    /// Index into `Scene::materials`.
+    pub fn material_index(&self) -> usize {
+        self.raw.mMaterialIndex as usize
+    }
+
+    fn vectors(&self, ptr: *const sys::aiVector3D) -> impl ExactSizeIterator<Item = Vec3> + 'a {
+        unsafe { raw_slice(ptr, self.raw.mNumVertices) }
+            .iter()
+            .map(|v| vec3(v.x, v.y, v.z))
+    }
+}
@@ -0,0 +1,138 @@
+use std::{ffi::CString, path::Path, ptr::NonNull};
+
+use glam::Mat4;
+
+use super::{
+    ai_string_to_string, raw_slice, sys, ImportError, Material, Mesh, PostProcess, Texture,
+};
+
+/// An imported scene, released when dropped.
+pub struct Scene {
+    raw: NonNull<sys::aiScene>,
+}
+
+impl Scene {
+    pub fn import(path: impl AsRef<Path>, flags: PostProcess) -> Result<Self, ImportError> {
+        let path = path.as_ref();
+        let c_path = CString::new(path.to_string_lossy().as_bytes())
+            .map_err(|err| ImportError::new(path.to_path_buf(), err.to_string()))?;
+
+        let raw = unsafe { sys::aiImportFile(c_path.as_ptr(), flags.bits()) };
+        let raw = NonNull::new(raw as *mut sys::aiScene)
+            .ok_or_else(|| ImportError::from_assimp(path.to_path_buf()))?;
+        let scene = Self { raw };
+
+        if scene.raw().mFlags & sys::AI_SCENE_FLAGS_INCOMPLETE != 0 {
+            return Err(ImportError::new(path.to_path_buf(), "Scene is incomplete"));
+        }
+        Ok(scene)
+    }
+
+    pub fn meshes(&self) -> impl ExactSizeIterator<Item = Mesh<'_>> {
+        let raw = self.raw();
+        unsafe { raw_slice(raw.mMeshes, raw.mNumMeshes) }
+            .iter()
+            .map(|mesh| Mesh::new(unsafe { &**mesh }))
+    }
+    pub fn mesh(&self, index: usize) -> Option<Mesh<'_>> {
+        let raw = self.raw();
+        unsafe { raw_slice(raw.mMeshes, raw.mNumMeshes) }
+            .get(index)
+            .map(|mesh| Mesh::new(unsafe { &**mesh }))
+    }
+
+    pub fn materials(&self) -> impl ExactSizeIterator<Item = Material<'_>> {
+        let raw = self.raw();
+        unsafe { raw_slice(raw.mMaterials, raw.mNumMaterials) }
+            .iter()
+            .map(|material| Material::new(unsafe { &**material }))
+    }
+    pub fn material(&self, index: usize) -> Option<Material<'_>> {
+        let raw = self.raw();
+        unsafe { raw_slice(raw.mMaterials, raw.mNumMaterials) }
+            .get(index)
+            .map(|material| Material::new(unsafe { &**material }))
+    }
+
+    /// Textures embedded in the model file itself.
+    pub fn textures(&self) -> impl ExactSizeIterator<Item = Texture<'_>> {
+        let raw = self.raw();
+        unsafe { raw_slice(raw.mTextures, raw.mNumTextures) }
+            .iter()
+            .map(|texture| Texture::new(unsafe { &**texture }))
+    }
+
+    /// Looks up an embedded texture from a material's texture path, either by `*<index>` or by
+    /// file name.
+    pub fn embedded_texture(&self, path: &str) -> Option<Texture<'_>> {
+        if let Some(index) = path.strip_prefix('*') {
+            return self.textures().nth(index.parse().ok()?);
+        }
+        let file_name = Path::new(path).file_name()?;
+        self.textures()
+            .find(|texture| Path::new(&texture.filename()).file_name() == Some(file_name))
+    }
+
+    pub fn root_node(&self) -> Option<Node<'_>> {
+        unsafe { self.raw().mRootNode.as_ref() }.map(Node::new)
+    }
+
+    /// Every node in the hierarchy, depth first starting at the root.
+    pub fn nodes(&self) -> impl Iterator<Item = Node<'_>> {
+        let mut stack: Vec<Node<'_>> = self.root_node().into_iter().collect();
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            let children: Vec<Node<'_>> = node.children().collect();
+            stack.extend(children.into_iter().rev());
+            Some(node)
+        })
+    }
+
+    fn raw(&self) -> &sys::aiScene {
+        unsafe { self.raw.as_ref() }
+    }
+}
+
+impl Drop for Scene {
+    fn drop(&mut self) {
+        unsafe { sys::aiReleaseImport(self.raw.as_ptr()) }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Node<'a> {
+    raw: &'a sys::aiNode,
+}
+
+impl<'a> Node<'a> {
+    fn new(raw: &'a sys::aiNode) -> Self {
+        Self { raw }
+    }
+
+    pub fn name(&self) -> String {
+        ai_string_to_string(&self.raw.mName)
+    }
+
+    /// Transform relative to the parent node.
+    pub fn transformation(&self) -> Mat4 {
+        let m = &self.raw.mTransformation;
+        // Assimp matrices are row major.
+        Mat4::from_cols_array(&[
+            m.a1, m.b1, m.c1, m.d1, //
+            m.a2, m.b2, m.c2, m.d2, //
+            m.a3, m.b3, m.c3, m.d3, //
+            m.a4, m.b4, m.c4, m.d4,
+        ])
+    }
+
+    /// Indices into `Scene::meshes` of the meshes attached to this node.
+    pub fn mesh_indices(&self) -> &'a [u32] {
+        unsafe { raw_slice(self.raw.mMeshes, self.raw.mNumMeshes) }
+    }
+
+    pub fn children(&self) -> impl ExactSizeIterator<Item = Node<'a>> {
+        unsafe { raw_slice(self.raw.mChildren, self.raw.mNumChildren) }
+            .iter()
+            .map(|child| Node::new(unsafe { &**child }))
+    }
+}
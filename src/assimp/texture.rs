@@ -0,0 +1,64 @@
+use std::ffi::CStr;
+
+use super::{ai_string_to_string, raw_slice, sys};
+
+/// A single texel of an uncompressed embedded texture, stored as BGRA.
+pub type Texel = [u8; 4];
+
+pub enum TextureData<'a> {
+    /// The raw bytes of a compressed file, e.g. a png, see `Texture::format_hint`. Assimp
+    /// stores their length in `mWidth`, as a byte count.
+    Compressed(&'a [u8]),
+    Uncompressed {
+        width: u32,
+        height: u32,
+        texels: &'a [Texel],
+    },
+}
+
+#[derive(Clone, Copy)]
+pub struct Texture<'a> {
+    raw: &'a sys::aiTexture,
+}
+
+impl<'a> Texture<'a> {
+    pub(super) fn new(raw: &'a sys::aiTexture) -> Self {
+        Self { raw }
+    }
+
+    pub fn filename(&self) -> String {
+        ai_string_to_string(&self.raw.mFilename)
+    }
+
+    /// File extension of compressed textures (e.g. "png"), or the channel layout of
+    /// uncompressed ones (e.g. "argb8888").
+    pub fn format_hint(&self) -> String {
+        let hint = &self.raw.achFormatHint;
+        let bytes: Vec<u8> = hint
+            .iter()
+            .take_while(|c| **c != 0)
+            .map(|c| *c as u8)
+            .chain(std::iter::once(0))
+            .collect();
+        CStr::from_bytes_with_nul(&bytes)
+            .map(|hint| hint.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    pub fn data(&self) -> TextureData<'a> {
+        let width = self.raw.mWidth;
+        let height = self.raw.mHeight;
+        if height == 0 {
+            // For compressed textures `mWidth` is the size of the file in bytes, not texels.
+            TextureData::Compressed(unsafe { raw_slice(self.raw.pcData as *const u8, width) })
+        } else {
+            // Texels are four bytes, so they can be viewed as arrays rather than aiTexels.
+            let texels = self.raw.pcData as *const Texel;
+            TextureData::Uncompressed {
+                width,
+                height,
+                texels: unsafe { raw_slice(texels, width * height) },
+            }
+        }
+    }
+}
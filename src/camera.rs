@@ -4,12 +4,31 @@ use direction::{Degrees, Direction};
 use glam::{vec3, Mat4, Vec3};
 use winit::keyboard::KeyCode;
 
+use crate::shader::{BufferBlock, Std140, CAMERA_BINDING};
+
 const SPEED: f32 = 2.0;
+const FOV_DEFAULT: Degrees = 80.0;
+pub const NEAR_PLANE: f32 = 0.1;
+pub const FAR_PLANE: f32 = 100.0;
 
 pub struct Camera {
     pos: Vec3,
     dir: Direction,
     up: Vec3,
+    fov: Degrees,
+}
+
+/// The `CameraBlock` uniform block, uploaded once per frame and shared by every program.
+#[derive(Clone, Copy, Std140, BufferBlock)]
+#[repr(C)]
+#[block(binding = CAMERA_BINDING)]
+pub struct CameraBlock {
+    pub view: Mat4,
+    pub projection: Mat4,
+    #[uniform(rename = "viewPos")]
+    pub view_pos: Vec3,
+    #[uniform(skip)]
+    _padding: f32,
 }
 
 impl Camera {
@@ -20,6 +39,31 @@ impl Camera {
     pub fn view_matrix(&self) -> Mat4 {
         Mat4::look_to_rh(self.pos, self.dir.euler(), self.up)
     }
+    pub fn projection_matrix(&self, aspect_ratio: f32) -> Mat4 {
+        Mat4::perspective_rh_gl(self.fov.to_radians(), aspect_ratio, NEAR_PLANE, FAR_PLANE)
+    }
+    /// World space corners of the slice of the view frustum between `near` and `far` from the
+    /// camera, the four nearer corners first.
+    pub fn frustum_corners(&self, aspect_ratio: f32, near: f32, far: f32) -> [Vec3; 8] {
+        let projection = Mat4::perspective_rh_gl(self.fov.to_radians(), aspect_ratio, near, far);
+        let inverse = (projection * self.view_matrix()).inverse();
+        let mut corners = [Vec3::ZERO; 8];
+        for (index, corner) in corners.iter_mut().enumerate() {
+            let x = if index & 1 == 0 { -1.0 } else { 1.0 };
+            let y = if index & 2 == 0 { -1.0 } else { 1.0 };
+            let z = if index & 4 == 0 { -1.0 } else { 1.0 };
+            *corner = inverse.project_point3(vec3(x, y, z));
+        }
+        corners
+    }
+    pub fn block(&self, aspect_ratio: f32) -> CameraBlock {
+        CameraBlock {
+            view: self.view_matrix(),
+            projection: self.projection_matrix(aspect_ratio),
+            view_pos: self.pos,
+            _padding: 0.0,
+        }
+    }
     pub fn pos(&self) -> Vec3 {
         self.pos
     }
@@ -70,6 +114,13 @@ impl Camera {
     pub fn adjust_yaw(&mut self, yaw: Degrees) {
         self.dir.adjust_yaw(yaw);
     }
+
+    pub fn fov(&self) -> Degrees {
+        self.fov
+    }
+    pub fn adjust_zoom(&mut self, degrees: Degrees) {
+        self.fov = (self.fov + degrees).clamp(5.0, 80.0);
+    }
 }
 
 impl Default for Camera {
@@ -86,6 +137,7 @@ impl Default for Camera {
             pos: camera_pos,
             dir: camera_dir,
             up: camera_up,
+            fov: FOV_DEFAULT,
         }
     }
 }
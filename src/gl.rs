@@ -37,7 +37,8 @@ pub fn get_gl_string(gl: &Gl, variant: GLenum) -> Option<&'static CStr> {
     }
 }
 
-pub unsafe fn create_shader(gl: &Gl, shader: GLenum, source: &[u8]) -> GLuint {
+/// Compiles a shader, returning the driver's info log if compilation fails.
+pub unsafe fn create_shader(gl: &Gl, shader: GLenum, source: &[u8]) -> Result<GLuint, String> {
     let shader = gl.CreateShader(shader);
     gl.ShaderSource(
         shader,
@@ -46,7 +47,45 @@ pub unsafe fn create_shader(gl: &Gl, shader: GLenum, source: &[u8]) -> GLuint {
         std::ptr::null(),
     );
     gl.CompileShader(shader);
-    shader
+
+    let mut status = types::GLint::from(FALSE);
+    gl.GetShaderiv(shader, COMPILE_STATUS, &mut status);
+    if status == types::GLint::from(FALSE) {
+        let log = get_shader_info_log(gl, shader);
+        gl.DeleteShader(shader);
+        return Err(log);
+    }
+    Ok(shader)
+}
+
+pub unsafe fn get_shader_info_log(gl: &Gl, shader: GLuint) -> String {
+    let mut len = 0;
+    gl.GetShaderiv(shader, INFO_LOG_LENGTH, &mut len);
+    let mut log = vec![0_u8; len.max(1) as usize];
+    let mut written = 0;
+    gl.GetShaderInfoLog(
+        shader,
+        log.len() as i32,
+        &mut written,
+        log.as_mut_ptr().cast(),
+    );
+    log.truncate(written as usize);
+    String::from_utf8_lossy(&log).into_owned()
+}
+
+pub unsafe fn get_program_info_log(gl: &Gl, program: GLuint) -> String {
+    let mut len = 0;
+    gl.GetProgramiv(program, INFO_LOG_LENGTH, &mut len);
+    let mut log = vec![0_u8; len.max(1) as usize];
+    let mut written = 0;
+    gl.GetProgramInfoLog(
+        program,
+        log.len() as i32,
+        &mut written,
+        log.as_mut_ptr().cast(),
+    );
+    log.truncate(written as usize);
+    String::from_utf8_lossy(&log).into_owned()
 }
 
 pub fn create_gl_context(window: &Window, gl_config: &Config) -> NotCurrentContext {
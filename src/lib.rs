@@ -7,6 +7,10 @@ pub mod mesh;
 pub mod object;
 pub mod renderer;
 pub mod shader;
+pub mod shadow;
 pub mod state;
 pub mod timer;
 pub mod assimp;
+
+// Lets code generated by `learn_ogl_rs_derive` name this crate from inside it.
+extern crate self as learn_ogl_rs;
@@ -1,14 +1,39 @@
 mod direction;
+mod list;
 mod point;
 mod spot;
 
-use glam::{Mat4, Vec3};
+use glam::Vec3;
 
-use crate::gl::{types::GLfloat, Gl};
+use crate::{
+    gl::Gl,
+    shader::{BufferBlock, Std140, LIGHTS_BINDING},
+};
 
-pub use self::direction::DirectionLight;
-pub use self::point::PointLight;
-pub use self::spot::SpotLight;
+pub use self::direction::{DirectionLight, DirectionLightAttributes, DirectionLightsBlock};
+pub use self::list::{BufferedLight, LightList};
+pub use self::point::{PointLight, PointLightAttributes, PointLightsBlock};
+pub use self::spot::{SpotLight, SpotLightAttributes, SpotLightsBlock};
+
+pub type DirectionLights = LightList<DirectionLight>;
+pub type PointLights = LightList<PointLight>;
+pub type SpotLights = LightList<SpotLight>;
+
+/// The `LightsBlock` uniform block, holding the number of lights of each kind. The lights
+/// themselves are in a storage block per kind, see `LightList`.
+#[derive(Clone, Copy, Default, Std140, BufferBlock)]
+#[repr(C)]
+#[block(binding = LIGHTS_BINDING)]
+pub struct LightsBlock {
+    #[uniform(rename = "dirLightCount")]
+    pub dir_light_count: u32,
+    #[uniform(rename = "spotLightCount")]
+    pub spot_light_count: u32,
+    #[uniform(rename = "pointLightCount")]
+    pub point_light_count: u32,
+    #[uniform(skip)]
+    _padding: u32,
+}
 
 pub trait Light {
     fn pos(&self) -> Vec3;
@@ -17,6 +42,50 @@ pub trait Light {
     fn dir(&self) -> Vec3;
     fn set_dir(&mut self, gl: &Gl, dir: Vec3) -> &mut dyn Light;
 
-    fn draw(&self, _gl: &Gl, _view_matrix: Mat4) {}
-    fn adjust_zoom(&mut self, _degrees: GLfloat) {}
+    /// Disabled lights are kept around but don't light anything.
+    fn enabled(&self) -> bool;
+    fn set_enabled(&mut self, enabled: bool) -> &mut dyn Light;
+
+    fn draw(&self, _gl: &Gl) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    /// Names of the members of the GLSL struct `name` in `source`, in order.
+    fn glsl_members<'a>(source: &'a str, name: &str) -> Vec<&'a str> {
+        let start = source.find(&format!("struct {name} {{")).unwrap();
+        let body = &source[start..];
+        let body = &body[body.find('{').unwrap() + 1..body.find("};").unwrap()];
+        body.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with("//"))
+            .map(|line| {
+                line.trim_end_matches(';')
+                    .split_whitespace()
+                    .last()
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_light_members_match_glsl() {
+        let source = fs::read_to_string("src/shader/lighting.glsl").unwrap();
+        for (name, members) in [
+            ("DirLight", DirectionLightAttributes::MEMBERS),
+            ("SpotLight", SpotLightAttributes::MEMBERS),
+            ("PointLight", PointLightAttributes::MEMBERS),
+        ] {
+            let rust_members: Vec<_> = members.iter().map(|(member, _)| *member).collect();
+            assert_eq!(rust_members, glsl_members(&source, name), "{name}");
+            assert!(
+                members.windows(2).all(|pair| pair[0].1 < pair[1].1),
+                "{name} members aren't in order"
+            );
+        }
+    }
 }
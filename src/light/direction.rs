@@ -1,13 +1,11 @@
-use std::rc::Rc;
-
 use glam::{vec3, Vec3};
 
 use crate::{
-    gl::Gl,
-    shader::{LightCasterShader, ShaderTrait},
+    gl::{types::GLuint, Gl},
+    shader::{Std140, StorageArray, DIRECTION_LIGHTS_BINDING},
 };
 
-use super::Light;
+use super::{BufferedLight, Light, LightsBlock};
 
 const DIRECTION_DEFAULT: Vec3 = vec3(0.0, 0.0, -1.0);
 
@@ -15,79 +13,95 @@ const AMBIENT_STRENGTH_DEFAULT: Vec3 = vec3(0.2, 0.2, 0.2);
 const DIFFUSE_STRENGTH_DEFAULT: Vec3 = vec3(0.5, 0.5, 0.5);
 const SPECULAR_STRENGTH_DEFAULT: Vec3 = vec3(1.0, 1.0, 1.0);
 
-struct DirectionLightAttributes {
-    bound_shader: Rc<LightCasterShader>,
-
+/// The GLSL `DirLight` struct.
+#[derive(Clone, Copy, Std140)]
+#[repr(C)]
+pub struct DirectionLightAttributes {
     direction: Vec3,
+    #[uniform(skip)]
+    _padding0: f32,
 
     // Strength of each type of lighting
     ambient: Vec3,
+    #[uniform(skip)]
+    _padding1: f32,
     diffuse: Vec3,
+    #[uniform(skip)]
+    _padding2: f32,
     specular: Vec3,
+    #[uniform(skip)]
+    _padding3: f32,
 }
 
-impl DirectionLightAttributes {
-    fn new(gl: &Gl, bound_shader: Rc<LightCasterShader>) -> Self {
-        let attrs = Self {
-            bound_shader,
-
+impl Default for DirectionLightAttributes {
+    fn default() -> Self {
+        Self {
             direction: DIRECTION_DEFAULT,
+            _padding0: 0.0,
 
             // Strength of each type of lighting
             ambient: AMBIENT_STRENGTH_DEFAULT,
+            _padding1: 0.0,
             diffuse: DIFFUSE_STRENGTH_DEFAULT,
+            _padding2: 0.0,
             specular: SPECULAR_STRENGTH_DEFAULT,
-        };
-        attrs.sync_state(gl);
-        attrs
+            _padding3: 0.0,
+        }
     }
+}
 
-    pub fn set_dir(&mut self, gl: &Gl, dir: Vec3) {
-        self.direction = dir;
-        self.sync_state(gl);
-    }
-    pub fn dir(&self) -> Vec3 {
-        self.direction
-    }
+/// The `DirLightsBlock` storage block, holding every enabled direction light.
+pub struct DirectionLightsBlock;
 
-    fn sync_state(&self, gl: &Gl) {
-        self.bound_shader
-            .shader
-            .set_vec3(gl, "dirLight.direction", self.direction.into())
-            .unwrap();
-
-        self.bound_shader
-            .shader
-            .set_vec3(gl, "dirLight.ambient", self.ambient.into())
-            .unwrap();
-        self.bound_shader
-            .shader
-            .set_vec3(gl, "dirLight.diffuse", self.diffuse.into())
-            .unwrap();
-        self.bound_shader
-            .shader
-            .set_vec3(gl, "dirLight.specular", self.specular.into())
-            .unwrap();
-    }
+impl StorageArray for DirectionLightsBlock {
+    type Element = DirectionLightAttributes;
+    const NAME: &'static str = "DirLightsBlock";
+    const BINDING: GLuint = DIRECTION_LIGHTS_BINDING;
 }
+
 pub struct DirectionLight {
     attrs: DirectionLightAttributes,
+    enabled: bool,
+    casts_shadow: bool,
 }
 impl DirectionLight {
-    pub fn new(gl: &Gl, lit_object_shader: Rc<LightCasterShader>) -> Self {
+    pub fn new(dir: Vec3) -> Self {
         Self {
-            attrs: DirectionLightAttributes::new(gl, lit_object_shader),
+            attrs: DirectionLightAttributes {
+                direction: dir,
+                ..Default::default()
+            },
+            enabled: true,
+            casts_shadow: false,
         }
     }
+
+    /// Only the first enabled light that casts shadows gets a shadow map, see
+    /// `DirectionalShadow`.
+    pub fn with_shadow(mut self) -> Self {
+        self.casts_shadow = true;
+        self
+    }
+    pub fn casts_shadow(&self) -> bool {
+        self.casts_shadow
+    }
+
+    /// Sets the strength of each type of lighting, which also gives the light its color.
+    pub fn with_strength(mut self, ambient: Vec3, diffuse: Vec3, specular: Vec3) -> Self {
+        self.attrs.ambient = ambient;
+        self.attrs.diffuse = diffuse;
+        self.attrs.specular = specular;
+        self
+    }
 }
 
 impl Light for DirectionLight {
-    fn set_dir(&mut self, gl: &Gl, dir: Vec3) -> &mut dyn Light {
-        self.attrs.set_dir(gl, dir);
+    fn set_dir(&mut self, _gl: &Gl, dir: Vec3) -> &mut dyn Light {
+        self.attrs.direction = dir;
         self
     }
     fn dir(&self) -> Vec3 {
-        self.attrs.dir()
+        self.attrs.direction
     }
 
     // This is a no-op since DirectionLights don't have positions.
@@ -97,4 +111,23 @@ impl Light for DirectionLight {
     fn pos(&self) -> Vec3 {
         Vec3::ZERO
     }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+    fn set_enabled(&mut self, enabled: bool) -> &mut dyn Light {
+        self.enabled = enabled;
+        self
+    }
+}
+
+impl BufferedLight for DirectionLight {
+    type Block = DirectionLightsBlock;
+
+    fn attributes(&self) -> DirectionLightAttributes {
+        self.attrs
+    }
+    fn set_count(lights_block: &mut LightsBlock, count: u32) {
+        lights_block.dir_light_count = count;
+    }
 }
@@ -0,0 +1,86 @@
+use std::rc::Rc;
+
+use crate::{
+    gl::Gl,
+    shader::{StorageArray, StorageBuffer, UniformBuffer},
+};
+
+use super::{Light, LightsBlock};
+
+/// A kind of light lit programs read from a storage block of their own, with the number of
+/// lights in `LightsBlock`.
+pub trait BufferedLight: Light {
+    type Block: StorageArray;
+
+    /// The light's GLSL struct.
+    fn attributes(&self) -> <Self::Block as StorageArray>::Element;
+    fn set_count(lights_block: &mut LightsBlock, count: u32);
+}
+
+/// Every light of one kind in the scene. Lights can be added and removed at any time, only the
+/// enabled ones are uploaded.
+pub struct LightList<L: BufferedLight> {
+    lights: Vec<L>,
+    buffer: StorageBuffer<L::Block>,
+    lights_block: Rc<UniformBuffer<LightsBlock>>,
+}
+
+impl<L: BufferedLight> LightList<L> {
+    pub fn new(gl: &Gl, lights_block: Rc<UniformBuffer<LightsBlock>>) -> Self {
+        Self {
+            lights: vec![],
+            buffer: StorageBuffer::new(gl),
+            lights_block,
+        }
+    }
+
+    pub fn add(&mut self, light: L) -> &mut L {
+        self.lights.push(light);
+        self.lights.last_mut().unwrap()
+    }
+
+    pub fn remove(&mut self, index: usize) -> L {
+        self.lights.remove(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lights.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&L> {
+        self.lights.get(index)
+    }
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut L> {
+        self.lights.get_mut(index)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &L> {
+        self.lights.iter()
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut L> {
+        self.lights.iter_mut()
+    }
+
+    /// Uploads the enabled lights, to be called once per frame before drawing anything they
+    /// light.
+    pub fn upload(&mut self) {
+        let attrs: Vec<_> = self
+            .lights
+            .iter()
+            .filter(|light| light.enabled())
+            .map(L::attributes)
+            .collect();
+        self.buffer.upload(&attrs);
+        self.lights_block
+            .update(|block| L::set_count(block, attrs.len() as u32));
+    }
+
+    /// Draws the enabled lights that have something to show for themselves, like a light cube.
+    pub fn draw(&self, gl: &Gl) {
+        for light in self.lights.iter().filter(|light| light.enabled()) {
+            light.draw(gl);
+        }
+    }
+}
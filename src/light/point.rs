@@ -1,18 +1,15 @@
 use std::rc::Rc;
 
-use glam::{vec3, Mat4, Vec3};
+use glam::{vec3, Vec3};
 
 use crate::{
-    camera::direction::Degrees,
-    gl::Gl,
+    gl::{types::GLuint, Gl},
     object::light_cube::LightCube,
     renderer::{VERTEX_DATA, VERTEX_DATA_STRIDE},
-    shader::{LightCasterShader, ShaderTrait},
+    shader::{LightCubeShader, Std140, StorageArray, POINT_LIGHTS_BINDING},
 };
 
-use super::Light;
-
-const POSITION_DEFAULT: Vec3 = vec3(0.0, 2.0, 0.0);
+use super::{BufferedLight, Light, LightsBlock};
 
 const AMBIENT_STRENGTH_DEFAULT: Vec3 = vec3(0.0, 0.0, 0.0);
 const DIFFUSE_STRENGTH_DEFAULT: Vec3 = vec3(0.5, 0.5, 0.5);
@@ -22,127 +19,123 @@ const ATTENUATION_CONSTANT_DEFAULT: f32 = 1.0;
 const ATTENUATION_LINEAR_DEFAULT: f32 = 0.09;
 const ATTENUATION_QUADRATIC_DEFAULT: f32 = 0.032;
 
-struct PointLightAttributes {
-    bound_shader: Rc<LightCasterShader>,
+/// How much brighter than its diffuse color the light's cube is drawn, so it glows.
+const LIGHT_CUBE_EMISSION: f32 = 8.0;
+
+const SHADOW_FAR_PLANE_DEFAULT: f32 = 25.0;
+const SHADOW_BIAS_DEFAULT: f32 = 0.05;
 
+/// The GLSL `PointLight` struct.
+#[derive(Clone, Copy, Std140)]
+#[repr(C)]
+pub struct PointLightAttributes {
     position: Vec3,
+    constant: f32,
 
-    // Strength of each type of lighting
+    // Strength of each type of lighting, followed by the rest of the attenuation terms
     ambient: Vec3,
+    linear: f32,
     diffuse: Vec3,
+    quadratic: f32,
     specular: Vec3,
 
-    // Attenuation
-    constant: f32,
-    linear: f32,
-    quadratic: f32,
+    /// Distance past which the light casts no shadows.
+    #[uniform(rename = "farPlane")]
+    far_plane: f32,
+    /// World space distance a fragment is moved toward the light before its shadow is looked up.
+    #[uniform(rename = "shadowBias")]
+    shadow_bias: f32,
+    /// The light's cube in the point shadow maps, -1 if it doesn't cast shadows.
+    #[uniform(rename = "shadowLayer")]
+    shadow_layer: i32,
+    #[uniform(skip)]
+    _padding0: f32,
+    #[uniform(skip)]
+    _padding1: f32,
 }
 
 impl PointLightAttributes {
-    fn new(gl: &Gl, bound_shader: Rc<LightCasterShader>, index: u8) -> Self {
-        let attrs = Self {
-            bound_shader,
-
-            position: POSITION_DEFAULT,
+    fn new(position: Vec3) -> Self {
+        Self {
+            position,
+            constant: ATTENUATION_CONSTANT_DEFAULT,
 
-            // Strength of each type of lighting
             ambient: AMBIENT_STRENGTH_DEFAULT,
+            linear: ATTENUATION_LINEAR_DEFAULT,
             diffuse: DIFFUSE_STRENGTH_DEFAULT,
+            quadratic: ATTENUATION_QUADRATIC_DEFAULT,
             specular: SPECULAR_STRENGTH_DEFAULT,
 
-            // Attenuation
-            constant: ATTENUATION_CONSTANT_DEFAULT,
-            linear: ATTENUATION_LINEAR_DEFAULT,
-            quadratic: ATTENUATION_QUADRATIC_DEFAULT,
-        };
-        attrs.sync_state(gl, index);
-        attrs
-    }
-    pub fn set_pos(&mut self, gl: &Gl, pos: Vec3, index: u8) {
-        self.position = pos;
-        self.sync_state(gl, index);
-    }
-    pub fn pos(&self) -> Vec3 {
-        self.position
-    }
-
-    fn sync_state(&self, gl: &Gl, index: u8) {
-        self.bound_shader
-            .shader
-            .set_vec3(
-                gl,
-                &format!("pointLights[{index}].position"),
-                self.position.into(),
-            )
-            .unwrap();
-
-        self.bound_shader
-            .shader
-            .set_vec3(
-                gl,
-                &format!("pointLights[{index}].ambient"),
-                self.ambient.into(),
-            )
-            .unwrap();
-        self.bound_shader
-            .shader
-            .set_vec3(
-                gl,
-                &format!("pointLights[{index}].diffuse"),
-                self.diffuse.into(),
-            )
-            .unwrap();
-        self.bound_shader
-            .shader
-            .set_vec3(
-                gl,
-                &format!("pointLights[{index}].specular"),
-                self.specular.into(),
-            )
-            .unwrap();
-
-        self.bound_shader
-            .shader
-            .set_float(gl, &format!("pointLights[{index}].constant"), self.constant)
-            .unwrap();
-        self.bound_shader
-            .shader
-            .set_float(gl, &format!("pointLights[{index}].linear"), self.linear)
-            .unwrap();
-        self.bound_shader
-            .shader
-            .set_float(
-                gl,
-                &format!("pointLights[{index}].quadratic"),
-                self.quadratic,
-            )
-            .unwrap();
+            far_plane: SHADOW_FAR_PLANE_DEFAULT,
+            shadow_bias: SHADOW_BIAS_DEFAULT,
+            shadow_layer: -1,
+            _padding0: 0.0,
+            _padding1: 0.0,
+        }
     }
 }
 
+/// The `PointLightsBlock` storage block, holding every enabled point light.
+pub struct PointLightsBlock;
+
+impl StorageArray for PointLightsBlock {
+    type Element = PointLightAttributes;
+    const NAME: &'static str = "PointLightsBlock";
+    const BINDING: GLuint = POINT_LIGHTS_BINDING;
+}
+
 pub struct PointLight {
-    index: u8,
     light_cube: LightCube,
     attrs: PointLightAttributes,
+    enabled: bool,
+    casts_shadow: bool,
 }
 
 impl PointLight {
-    pub fn new(gl: &Gl, lit_object_shader: Rc<LightCasterShader>, index: u8) -> Self {
-        let light_cube = LightCube::new(gl, POSITION_DEFAULT, &VERTEX_DATA, VERTEX_DATA_STRIDE);
+    pub fn new(gl: &Gl, pos: Vec3, light_cube_shader: Rc<LightCubeShader>) -> Self {
+        let light_cube =
+            LightCube::new(gl, pos, light_cube_shader, &VERTEX_DATA, VERTEX_DATA_STRIDE);
         Self {
-            index,
             light_cube,
-            attrs: PointLightAttributes::new(gl, lit_object_shader, index),
+            attrs: PointLightAttributes::new(pos),
+            enabled: true,
+            casts_shadow: false,
         }
     }
+
+    /// Shadows are off by default since every light casting them renders the scene six more
+    /// times a frame.
+    pub fn with_shadow(mut self) -> Self {
+        self.casts_shadow = true;
+        self
+    }
+    pub fn casts_shadow(&self) -> bool {
+        self.casts_shadow
+    }
+    pub fn set_casts_shadow(&mut self, casts_shadow: bool) {
+        self.casts_shadow = casts_shadow;
+    }
+
+    pub fn far_plane(&self) -> f32 {
+        self.attrs.far_plane
+    }
+    pub fn set_shadow_settings(&mut self, far_plane: f32, bias: f32) {
+        self.attrs.far_plane = far_plane;
+        self.attrs.shadow_bias = bias;
+    }
+
+    /// Set by `PointShadows` to the cube the light's shadow was rendered into.
+    pub fn set_shadow_layer(&mut self, layer: Option<u32>) {
+        self.attrs.shadow_layer = layer.map_or(-1, |layer| layer as i32);
+    }
 }
 
 impl Light for PointLight {
     fn pos(&self) -> Vec3 {
-        self.attrs.pos()
+        self.attrs.position
     }
-    fn set_pos(&mut self, gl: &Gl, pos: Vec3) -> &mut dyn Light {
-        self.attrs.set_pos(gl, pos, self.index);
+    fn set_pos(&mut self, _gl: &Gl, pos: Vec3) -> &mut dyn Light {
+        self.attrs.position = pos;
         self.light_cube.set_pos(pos);
         self
     }
@@ -154,10 +147,28 @@ impl Light for PointLight {
     fn set_dir(&mut self, _gl: &Gl, _dir: Vec3) -> &mut dyn Light {
         self
     }
-    fn draw(&self, gl: &Gl, view_matrix: Mat4) {
-        self.light_cube.draw(gl, view_matrix)
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+    fn set_enabled(&mut self, enabled: bool) -> &mut dyn Light {
+        self.enabled = enabled;
+        self
+    }
+
+    fn draw(&self, gl: &Gl) {
+        self.light_cube
+            .draw(gl, self.attrs.diffuse * LIGHT_CUBE_EMISSION)
+    }
+}
+
+impl BufferedLight for PointLight {
+    type Block = PointLightsBlock;
+
+    fn attributes(&self) -> PointLightAttributes {
+        self.attrs
     }
-    fn adjust_zoom(&mut self, degrees: Degrees) {
-        self.light_cube.adjust_zoom(degrees);
+    fn set_count(lights_block: &mut LightsBlock, count: u32) {
+        lights_block.point_light_count = count;
     }
 }
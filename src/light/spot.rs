@@ -1,13 +1,11 @@
-use std::rc::Rc;
-
-use glam::{vec3, Vec3};
+use glam::{vec3, Mat4, Vec3, Vec4};
 
 use crate::{
-    gl::Gl,
-    shader::{LightCasterShader, ShaderTrait},
+    gl::{types::GLuint, Gl},
+    shader::{Std140, StorageArray, SPOT_LIGHTS_BINDING},
 };
 
-use super::Light;
+use super::{BufferedLight, Light, LightsBlock};
 
 const POSITION_DEFAULT: Vec3 = vec3(0.0, 2.0, 0.0);
 const DIRECTION_DEFAULT: Vec3 = vec3(0.0, 0.0, -1.0);
@@ -22,144 +20,190 @@ const ATTENUATION_CONSTANT_DEFAULT: f32 = 1.0;
 const ATTENUATION_LINEAR_DEFAULT: f32 = 0.09;
 const ATTENUATION_QUADRATIC_DEFAULT: f32 = 0.032;
 
-struct SpotLightAttributes {
-    bound_shader: Rc<LightCasterShader>,
+const SHADOW_NEAR_PLANE: f32 = 0.1;
+const SHADOW_FAR_PLANE_DEFAULT: f32 = 50.0;
+const SHADOW_BIAS_DEFAULT: f32 = 0.0001;
 
+/// The GLSL `SpotLight` struct.
+#[derive(Clone, Copy, Std140)]
+#[repr(C)]
+pub struct SpotLightAttributes {
     position: Vec3,
-    direction: Vec3,
+    /// Cosine of the angle, see `cutoff_cos`.
+    #[uniform(rename = "innerCutOff")]
     inner_cutoff: f32,
+    direction: Vec3,
+    #[uniform(rename = "outerCutOff")]
     outer_cutoff: f32,
 
-    // Strength of each type of lighting
+    // Strength of each type of lighting, each followed by an attenuation term
     ambient: Vec3,
-    diffuse: Vec3,
-    specular: Vec3,
-
-    // Attenuation
     constant: f32,
+    diffuse: Vec3,
     linear: f32,
+    specular: Vec3,
     quadratic: f32,
-}
 
-impl SpotLightAttributes {
-    fn new(gl: &Gl, bound_shader: Rc<LightCasterShader>) -> Self {
-        let attrs = Self {
-            bound_shader,
+    /// Projects world space into the light's shadow map, see `SpotLight::shadow_matrix`.
+    #[uniform(rename = "lightSpaceMatrix")]
+    light_space_matrix: Mat4,
+    /// Where in the spot shadow atlas the light's shadow map is, see `AtlasTile::uv_rect`.
+    #[uniform(rename = "shadowRect")]
+    shadow_rect: Vec4,
+    #[uniform(rename = "shadowBias")]
+    shadow_bias: f32,
+    /// Nonzero if the light got a tile of the atlas this frame.
+    #[uniform(rename = "hasShadow")]
+    has_shadow: u32,
+    #[uniform(skip)]
+    _padding0: f32,
+    #[uniform(skip)]
+    _padding1: f32,
+}
 
+impl Default for SpotLightAttributes {
+    fn default() -> Self {
+        Self {
             position: POSITION_DEFAULT,
+            inner_cutoff: cutoff_cos(INNER_CUTOFF_DEFAULT),
             direction: DIRECTION_DEFAULT,
-            inner_cutoff: INNER_CUTOFF_DEFAULT,
-            outer_cutoff: OUTER_CUTOFF_DEFAULT,
+            outer_cutoff: cutoff_cos(OUTER_CUTOFF_DEFAULT),
 
-            // Strength of each type of lighting
             ambient: AMBIENT_STRENGTH_DEFAULT,
-            diffuse: DIFFUSE_STRENGTH_DEFAULT,
-            specular: SPECULAR_STRENGTH_DEFAULT,
-
-            // Attenuation
             constant: ATTENUATION_CONSTANT_DEFAULT,
+            diffuse: DIFFUSE_STRENGTH_DEFAULT,
             linear: ATTENUATION_LINEAR_DEFAULT,
+            specular: SPECULAR_STRENGTH_DEFAULT,
             quadratic: ATTENUATION_QUADRATIC_DEFAULT,
-        };
-        attrs.sync_state(gl);
-        attrs
-    }
-    pub fn set_pos(&mut self, gl: &Gl, pos: Vec3) {
-        self.position = pos;
-        self.sync_state(gl);
-    }
-    pub fn pos(&self) -> Vec3 {
-        self.position
-    }
-
-    pub fn set_dir(&mut self, gl: &Gl, dir: Vec3) {
-        self.direction = dir;
-        self.sync_state(gl);
-    }
-    pub fn dir(&self) -> Vec3 {
-        self.direction
-    }
-
-    fn sync_state(&self, gl: &Gl) {
-        self.bound_shader
-            .shader
-            .set_vec3(gl, "spotLight.position", self.position.into())
-            .unwrap();
-        self.bound_shader
-            .shader
-            .set_vec3(gl, "spotLight.direction", self.direction.into())
-            .unwrap();
-        self.bound_shader
-            .shader
-            .set_float(
-                gl,
-                "spotLight.innerCutOff",
-                self.inner_cutoff.to_radians().cos(),
-            )
-            .unwrap();
-        self.bound_shader
-            .shader
-            .set_float(
-                gl,
-                "spotLight.outerCutOff",
-                self.outer_cutoff.to_radians().cos(),
-            )
-            .unwrap();
-
-        self.bound_shader
-            .shader
-            .set_vec3(gl, "spotLight.ambient", self.ambient.into())
-            .unwrap();
-        self.bound_shader
-            .shader
-            .set_vec3(gl, "spotLight.diffuse", self.diffuse.into())
-            .unwrap();
-        self.bound_shader
-            .shader
-            .set_vec3(gl, "spotLight.specular", self.specular.into())
-            .unwrap();
-
-        self.bound_shader
-            .shader
-            .set_float(gl, "spotLight.constant", self.constant)
-            .unwrap();
-        self.bound_shader
-            .shader
-            .set_float(gl, "spotLight.linear", self.linear)
-            .unwrap();
-        self.bound_shader
-            .shader
-            .set_float(gl, "spotLight.quadratic", self.quadratic)
-            .unwrap();
+
+            light_space_matrix: Mat4::IDENTITY,
+            shadow_rect: Vec4::ZERO,
+            shadow_bias: SHADOW_BIAS_DEFAULT,
+            has_shadow: 0,
+            _padding0: 0.0,
+            _padding1: 0.0,
+        }
     }
 }
 
+/// The shader compares against the cosine of the angle instead of the angle in degrees.
+fn cutoff_cos(degrees: f32) -> f32 {
+    degrees.to_radians().cos()
+}
+
+/// The `SpotLightsBlock` storage block, holding every enabled spot light.
+pub struct SpotLightsBlock;
+
+impl StorageArray for SpotLightsBlock {
+    type Element = SpotLightAttributes;
+    const NAME: &'static str = "SpotLightsBlock";
+    const BINDING: GLuint = SPOT_LIGHTS_BINDING;
+}
+
 pub struct SpotLight {
     attrs: SpotLightAttributes,
+    enabled: bool,
+    casts_shadow: bool,
+    shadow_far_plane: f32,
 }
 
 impl SpotLight {
-    pub fn new(gl: &Gl, lit_object_shader: Rc<LightCasterShader>) -> Self {
+    pub fn new(pos: Vec3, dir: Vec3) -> Self {
         Self {
-            attrs: SpotLightAttributes::new(gl, lit_object_shader),
+            attrs: SpotLightAttributes {
+                position: pos,
+                direction: dir,
+                ..Default::default()
+            },
+            enabled: true,
+            casts_shadow: false,
+            shadow_far_plane: SHADOW_FAR_PLANE_DEFAULT,
         }
     }
+
+    /// Sets the strength of each type of lighting, which also gives the light its color.
+    pub fn with_strength(mut self, ambient: Vec3, diffuse: Vec3, specular: Vec3) -> Self {
+        self.attrs.ambient = ambient;
+        self.attrs.diffuse = diffuse;
+        self.attrs.specular = specular;
+        self
+    }
+
+    /// Sets the angles in degrees at which the light starts fading out and is fully faded out.
+    pub fn with_cutoff(mut self, inner: f32, outer: f32) -> Self {
+        self.attrs.inner_cutoff = cutoff_cos(inner);
+        self.attrs.outer_cutoff = cutoff_cos(outer);
+        self
+    }
+
+    /// Has the light cast shadows, see `SpotShadows`.
+    pub fn with_shadow(mut self) -> Self {
+        self.casts_shadow = true;
+        self
+    }
+    pub fn casts_shadow(&self) -> bool {
+        self.casts_shadow
+    }
+    pub fn set_casts_shadow(&mut self, casts_shadow: bool) {
+        self.casts_shadow = casts_shadow;
+    }
+    pub fn set_shadow_settings(&mut self, far_plane: f32, bias: f32) {
+        self.shadow_far_plane = far_plane;
+        self.attrs.shadow_bias = bias;
+    }
+
+    /// A perspective projection from the light whose field of view just covers the outer cone.
+    pub fn shadow_matrix(&self) -> Mat4 {
+        let fov = 2.0 * self.attrs.outer_cutoff.clamp(-1.0, 1.0).acos();
+        let projection =
+            Mat4::perspective_rh_gl(fov, 1.0, SHADOW_NEAR_PLANE, self.shadow_far_plane);
+        let dir = self.attrs.direction.normalize();
+        let up = if dir.y.abs() > 0.99 { Vec3::Z } else { Vec3::Y };
+        projection * Mat4::look_to_rh(self.attrs.position, dir, up)
+    }
+
+    /// Set by `SpotShadows` to where the light's shadow map was rendered to, `None` if it
+    /// wasn't.
+    pub fn set_shadow_rect(&mut self, rect: Option<Vec4>) {
+        self.attrs.light_space_matrix = self.shadow_matrix();
+        self.attrs.shadow_rect = rect.unwrap_or(Vec4::ZERO);
+        self.attrs.has_shadow = rect.is_some().into();
+    }
 }
 
 impl Light for SpotLight {
     fn pos(&self) -> Vec3 {
-        self.attrs.pos()
+        self.attrs.position
     }
-    fn set_pos(&mut self, gl: &Gl, pos: Vec3) -> &mut dyn Light {
-        self.attrs.set_pos(gl, pos);
+    fn set_pos(&mut self, _gl: &Gl, pos: Vec3) -> &mut dyn Light {
+        self.attrs.position = pos;
         self
     }
 
     fn dir(&self) -> Vec3 {
-        self.attrs.dir()
+        self.attrs.direction
+    }
+    fn set_dir(&mut self, _gl: &Gl, dir: Vec3) -> &mut dyn Light {
+        self.attrs.direction = dir;
+        self
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
     }
-    fn set_dir(&mut self, gl: &Gl, dir: Vec3) -> &mut dyn Light {
-        self.attrs.set_dir(gl, dir);
+    fn set_enabled(&mut self, enabled: bool) -> &mut dyn Light {
+        self.enabled = enabled;
         self
     }
 }
+
+impl BufferedLight for SpotLight {
+    type Block = SpotLightsBlock;
+
+    fn attributes(&self) -> SpotLightAttributes {
+        self.attrs
+    }
+    fn set_count(lights_block: &mut LightsBlock, count: u32) {
+        lights_block.spot_light_count = count;
+    }
+}
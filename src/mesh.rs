@@ -1,15 +1,19 @@
-use std::ffi::CString;
+mod layout;
 
 use glam::{vec3, Mat4, Vec3};
 
 use crate::{
     gl::{
         self,
-        types::{GLfloat, GLint, GLuint},
+        types::{GLenum, GLfloat, GLsizei, GLuint},
         Gl,
     },
     helper::get_rand_angle,
-    shader::{DrawableShader, ShaderTrait, UniformGetSet},
+    shader::{DrawableShader, Shader, ShaderTrait, UniformGetSet},
+};
+
+pub use self::layout::{
+    AttributeLocation, AttributeType, LayoutError, VertexAttribute, VertexLayout,
 };
 
 struct Transform {
@@ -21,7 +25,6 @@ struct Transform {
 pub struct Mesh {
     vertex_buffer: VertexBuffer,
     transform: Transform,
-    fov: f32,
     texture_blend: GLfloat,
 }
 
@@ -29,14 +32,92 @@ pub struct VertexBuffer {
     vbo: u32,
     vao: u32,
     bindingindex: u32,
+    /// Buffers for the bindings after `bindingindex`, see `VertexLayout::next_binding`.
+    extra_buffers: Vec<GLuint>,
+    vbo_size: usize,
+    vertex_count: GLsizei,
+    topology: Topology,
+    index_buffer: Option<IndexBuffer>,
+}
+
+/// How vertices are assembled into primitives when drawn.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Topology {
+    Points,
+    Lines,
+    LineStrip,
+    LineLoop,
+    #[default]
+    Triangles,
+    TriangleStrip,
+    TriangleFan,
+}
+
+impl Topology {
+    fn gl_mode(self) -> GLenum {
+        match self {
+            Topology::Points => gl::POINTS,
+            Topology::Lines => gl::LINES,
+            Topology::LineStrip => gl::LINE_STRIP,
+            Topology::LineLoop => gl::LINE_LOOP,
+            Topology::Triangles => gl::TRIANGLES,
+            Topology::TriangleStrip => gl::TRIANGLE_STRIP,
+            Topology::TriangleFan => gl::TRIANGLE_FAN,
+        }
+    }
+}
+
+/// Element data for indexed drawing, u16 indices halve the upload size when a mesh has few
+/// enough vertices.
+#[derive(Clone, Copy)]
+pub enum Indices<'a> {
+    U16(&'a [u16]),
+    U32(&'a [u32]),
+}
+
+impl Indices<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Indices::U16(indices) => indices.len(),
+            Indices::U32(indices) => indices.len(),
+        }
+    }
+    fn gl_type(&self) -> GLenum {
+        match self {
+            Indices::U16(_) => gl::UNSIGNED_SHORT,
+            Indices::U32(_) => gl::UNSIGNED_INT,
+        }
+    }
+}
+
+struct IndexBuffer {
+    ebo: GLuint,
+    index_type: GLenum,
+    index_count: GLsizei,
 }
 
 impl VertexBuffer {
+    /// `stride` is the number of floats per vertex, the vertex count is derived from it. Panics
+    /// if it isn't positive.
     pub fn new(gl: &Gl, buffer: &[f32], stride: i32) -> Self {
+        assert!(
+            stride > 0,
+            "Vertex buffer stride has to be positive, got {stride}"
+        );
+        debug_assert!(
+            buffer.len().is_multiple_of(stride as usize),
+            "Vertex buffer of {} floats isn't a multiple of its stride {stride}",
+            buffer.len()
+        );
         let mut vertex_buffer = Self {
             vbo: 0,
             vao: 0,
             bindingindex: 0,
+            extra_buffers: vec![],
+            vbo_size: size_of_val(buffer),
+            vertex_count: (buffer.len() / stride as usize) as GLsizei,
+            topology: Topology::default(),
+            index_buffer: None,
         };
 
         unsafe {
@@ -65,35 +146,92 @@ impl VertexBuffer {
         vertex_buffer
     }
 
-    pub fn set_float_attribute_position(
-        &self,
-        gl: &Gl,
-        shader_attribute_name: &str,
-        program: u32,
-        start: u32,
-        length: u32,
-    ) {
+    pub fn new_indexed(gl: &Gl, buffer: &[f32], stride: i32, indices: Indices) -> Self {
+        let mut vertex_buffer = Self::new(gl, buffer, stride);
+        vertex_buffer.set_indices(gl, indices);
+        vertex_buffer
+    }
+
+    /// Uploads `indices` into an element buffer attached to the vao, replacing any previous one.
+    pub fn set_indices(&mut self, gl: &Gl, indices: Indices) {
+        let mut ebo: GLuint = 0;
         unsafe {
-            let c_shader_attribute_name = CString::new(shader_attribute_name).unwrap();
-            let attrib = gl.GetAttribLocation(
-                program,
-                c_shader_attribute_name.as_ptr() as *const gl::types::GLchar,
-            );
-            if attrib == -1 {
-                panic!("\nAttribute not found! Attribute Name: {shader_attribute_name}\n")
+            if let Some(index_buffer) = self.index_buffer.take() {
+                gl.DeleteBuffers(1, &index_buffer.ebo);
             }
 
-            gl.EnableVertexArrayAttrib(self.vao(), attrib as u32);
-            gl.VertexArrayAttribFormat(
-                self.vao(),
-                attrib as u32,
-                length as GLint,
-                gl::FLOAT,
-                gl::FALSE,
-                (start as usize * std::mem::size_of::<f32>()) as gl::types::GLuint,
+            gl.CreateBuffers(1, &mut ebo);
+            let (size, data) = match indices {
+                Indices::U16(indices) => (size_of_val(indices), indices.as_ptr().cast()),
+                Indices::U32(indices) => (size_of_val(indices), indices.as_ptr().cast()),
+            };
+            gl.NamedBufferData(ebo, size as gl::types::GLsizeiptr, data, gl::STATIC_DRAW);
+            gl.VertexArrayElementBuffer(self.vao(), ebo);
+        }
+
+        self.index_buffer = Some(IndexBuffer {
+            ebo,
+            index_type: indices.gl_type(),
+            index_count: indices.len() as GLsizei,
+        });
+    }
+
+    /// Uploads another buffer for the next unused binding, returning its binding index. Used
+    /// for attributes stored separately rather than interleaved in the main buffer.
+    pub fn add_buffer<T: Copy>(&mut self, gl: &Gl, data: &[T]) -> u32 {
+        let mut buffer: GLuint = 0;
+        unsafe {
+            gl.CreateBuffers(1, &mut buffer);
+            gl.NamedBufferData(
+                buffer,
+                size_of_val(data) as gl::types::GLsizeiptr,
+                data.as_ptr().cast(),
+                gl::STATIC_DRAW,
             );
-            gl.VertexArrayAttribBinding(self.vao(), attrib as u32, self.bindingindex);
         }
+        self.extra_buffers.push(buffer);
+        self.bindingindex + self.extra_buffers.len() as u32
+    }
+
+    /// Attaches every buffer with the stride from `layout` and points the attributes of
+    /// `shader` at them. The vertex count is rederived from the layout's stride.
+    ///
+    /// Attributes are only resolved here, so the vertex shader should pin them with
+    /// `layout (location = ...)` for them to stay put when the shader is reloaded.
+    pub fn set_layout(
+        &mut self,
+        gl: &Gl,
+        layout: &VertexLayout,
+        shader: &Shader,
+    ) -> Result<(), LayoutError> {
+        if layout.binding_count() > 1 + self.extra_buffers.len() as u32 {
+            return Err(LayoutError::MissingBuffer(
+                self.extra_buffers.len() as u32 + 1,
+            ));
+        }
+        layout.apply(gl, self.vao(), shader)?;
+
+        let buffers = std::iter::once(self.vbo).chain(self.extra_buffers.iter().copied());
+        for (binding, buffer) in (self.bindingindex..).zip(buffers) {
+            if binding >= layout.binding_count() {
+                break;
+            }
+            unsafe {
+                gl.VertexArrayVertexBuffer(
+                    self.vao(),
+                    binding,
+                    buffer,
+                    0,
+                    layout.stride(binding) as GLsizei,
+                );
+            }
+        }
+
+        let stride = layout.stride(self.bindingindex) as usize;
+        if let Some(vertex_count) = self.vbo_size.checked_div(stride) {
+            self.vertex_count = vertex_count as GLsizei;
+        }
+        Ok(())
     }
 
     pub fn vbo(&self) -> GLuint {
@@ -102,18 +240,54 @@ impl VertexBuffer {
     pub fn vao(&self) -> GLuint {
         self.vao
     }
+    pub fn vertex_count(&self) -> GLsizei {
+        self.vertex_count
+    }
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.set_topology(topology);
+        self
+    }
+    pub fn is_indexed(&self) -> bool {
+        self.index_buffer.is_some()
+    }
+
+    /// Issues the draw call for the bound vao, using the element buffer if there is one.
+    fn draw(&self, gl: &Gl) {
+        unsafe {
+            gl.BindVertexArray(self.vao());
+            match &self.index_buffer {
+                Some(index_buffer) => gl.DrawElements(
+                    self.topology.gl_mode(),
+                    index_buffer.index_count,
+                    index_buffer.index_type,
+                    std::ptr::null(),
+                ),
+                None => gl.DrawArrays(self.topology.gl_mode(), 0, self.vertex_count),
+            }
+        }
+    }
 }
 
 impl Mesh {
+    /// Starts out rotated by a random angle, see `with_rotation` for a fixed one.
     pub fn new(translation: Vec3, vertex_buffer: VertexBuffer) -> Self {
+        Self::with_rotation(translation, get_rand_angle(), vertex_buffer)
+    }
+
+    pub fn with_rotation(translation: Vec3, degrees: GLfloat, vertex_buffer: VertexBuffer) -> Self {
         Mesh {
             vertex_buffer,
             transform: Transform {
-                rotation: get_rand_angle(),
+                rotation: degrees,
                 translation,
                 scale: vec3(1.0, 1.0, 1.0),
             },
-            fov: 80.0,
             texture_blend: 0.2,
         }
     }
@@ -130,10 +304,6 @@ impl Mesh {
         transform.rotation += degrees;
     }
 
-    pub fn adjust_zoom(&mut self, degrees: GLfloat) {
-        self.fov = (self.fov + degrees).clamp(5.0, 80.0);
-    }
-
     pub fn adjust_scale(&mut self, scale: Vec3) {
         self.transform.scale =
             (self.transform.scale * scale).clamp(vec3(0.1, 0.1, 0.1), vec3(10.0, 10.0, 10.0));
@@ -151,7 +321,15 @@ impl Mesh {
         self.vertex_buffer.vao()
     }
 
-    pub fn draw(&self, gl: &Gl, view_matrix: Mat4, shader: &dyn DrawableShader) {
+    pub fn topology(&self) -> Topology {
+        self.vertex_buffer.topology()
+    }
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.vertex_buffer.set_topology(topology);
+    }
+
+    /// Draws with `shader`, which gets the view and projection from the camera's block.
+    pub fn draw(&self, gl: &Gl, shader: &dyn DrawableShader) {
         // self.rotate_by(1.0);
         let transform = &self.transform;
 
@@ -161,18 +339,10 @@ impl Mesh {
             * Mat4::from_rotation_y(transform.rotation.to_radians())
             * Mat4::from_scale(transform.scale);
 
-        let projection_matrix =
-            Mat4::perspective_rh_gl(self.fov.to_radians(), gl.get_aspect_ratio(), 0.1, 100.0);
-
         shader.model().set(model_matrix);
-        shader.view().set(view_matrix);
-        shader.projection().set(projection_matrix);
 
         shader.shader().enable(gl);
-        unsafe {
-            gl.BindVertexArray(self.vao());
-            gl.DrawArrays(gl::TRIANGLES, 0, 36);
-        }
+        self.vertex_buffer.draw(gl);
     }
 }
 
@@ -0,0 +1,301 @@
+use std::fmt;
+
+use crate::{
+    gl::{
+        self,
+        types::{GLenum, GLint, GLuint},
+        Gl,
+    },
+    shader::Shader,
+};
+
+/// Component type of a vertex attribute as stored in the buffer. The `*Norm` variants are
+/// normalized to `[0, 1]` (or `[-1, 1]` when signed) floats in the shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeType {
+    F32,
+    F16,
+    I32,
+    U32,
+    U8Norm,
+    I8Norm,
+    U16Norm,
+    I16Norm,
+}
+
+impl AttributeType {
+    pub fn size(self) -> u32 {
+        match self {
+            AttributeType::F32 | AttributeType::I32 | AttributeType::U32 => 4,
+            AttributeType::F16 | AttributeType::U16Norm | AttributeType::I16Norm => 2,
+            AttributeType::U8Norm | AttributeType::I8Norm => 1,
+        }
+    }
+
+    fn gl_type(self) -> GLenum {
+        match self {
+            AttributeType::F32 => gl::FLOAT,
+            AttributeType::F16 => gl::HALF_FLOAT,
+            AttributeType::I32 => gl::INT,
+            AttributeType::U32 => gl::UNSIGNED_INT,
+            AttributeType::U8Norm => gl::UNSIGNED_BYTE,
+            AttributeType::I8Norm => gl::BYTE,
+            AttributeType::U16Norm => gl::UNSIGNED_SHORT,
+            AttributeType::I16Norm => gl::SHORT,
+        }
+    }
+
+    fn normalized(self) -> bool {
+        matches!(
+            self,
+            AttributeType::U8Norm
+                | AttributeType::I8Norm
+                | AttributeType::U16Norm
+                | AttributeType::I16Norm
+        )
+    }
+
+    /// Integer attributes are read as `int`/`uint` in the shader instead of being converted to
+    /// floats.
+    fn is_integer(self) -> bool {
+        matches!(self, AttributeType::I32 | AttributeType::U32)
+    }
+}
+
+/// Either the name of the `in` variable in the vertex shader, or its explicit location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttributeLocation {
+    Name(String),
+    Index(GLuint),
+}
+
+impl fmt::Display for AttributeLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttributeLocation::Name(name) => write!(f, "{name}"),
+            AttributeLocation::Index(index) => write!(f, "location {index}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VertexAttribute {
+    pub location: AttributeLocation,
+    pub components: u32,
+    pub attribute_type: AttributeType,
+    /// Offset in bytes from the start of a vertex in its binding.
+    pub offset: u32,
+    pub binding: u32,
+}
+
+/// Describes where each vertex attribute lives in one or more buffers. Attributes added after
+/// `next_binding` are read from the next buffer, so attributes can be interleaved in a single
+/// buffer or split across several.
+/// ```
+/// # use learn_ogl_rs::mesh::{AttributeType, VertexLayout};
+/// let layout = VertexLayout::new()
+///     .attribute("aPos", 3, AttributeType::F32)
+///     .attribute("aNormal", 3, AttributeType::F32)
+///     .next_binding()
+///     .attribute("aTexCoords", 2, AttributeType::U16Norm);
+/// assert_eq!(layout.stride(0), 24);
+/// assert_eq!(layout.stride(1), 4);
+/// ```
+#[derive(Debug, Clone)]
+pub struct VertexLayout {
+    attributes: Vec<VertexAttribute>,
+    /// Stride in bytes of each binding.
+    strides: Vec<u32>,
+}
+
+impl Default for VertexLayout {
+    fn default() -> Self {
+        Self {
+            attributes: vec![],
+            strides: vec![0],
+        }
+    }
+}
+
+impl VertexLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an attribute to the current binding, located by its name in the shader.
+    pub fn attribute(self, name: &str, components: u32, attribute_type: AttributeType) -> Self {
+        self.push(
+            AttributeLocation::Name(name.to_string()),
+            components,
+            attribute_type,
+        )
+    }
+
+    /// Appends an attribute to the current binding, located by its `layout (location = ...)`.
+    pub fn attribute_at(
+        self,
+        location: GLuint,
+        components: u32,
+        attribute_type: AttributeType,
+    ) -> Self {
+        self.push(
+            AttributeLocation::Index(location),
+            components,
+            attribute_type,
+        )
+    }
+
+    /// Skips bytes in the current binding that no attribute reads.
+    pub fn padding(mut self, bytes: u32) -> Self {
+        *self.strides.last_mut().unwrap() += bytes;
+        self
+    }
+
+    /// Following attributes are read from a separate buffer.
+    pub fn next_binding(mut self) -> Self {
+        self.strides.push(0);
+        self
+    }
+
+    pub fn attributes(&self) -> &[VertexAttribute] {
+        &self.attributes
+    }
+    pub fn binding_count(&self) -> u32 {
+        self.strides.len() as u32
+    }
+    pub fn stride(&self, binding: u32) -> u32 {
+        self.strides[binding as usize]
+    }
+
+    fn push(
+        mut self,
+        location: AttributeLocation,
+        components: u32,
+        attribute_type: AttributeType,
+    ) -> Self {
+        let binding = self.strides.len() as u32 - 1;
+        let stride = self.strides.last_mut().unwrap();
+        self.attributes.push(VertexAttribute {
+            location,
+            components,
+            attribute_type,
+            offset: *stride,
+            binding,
+        });
+        *stride += components * attribute_type.size();
+        self
+    }
+
+    /// Resolves every attribute to a location in `shader`, checking that the layout and the
+    /// active attributes in its reflection agree.
+    pub fn resolve(&self, shader: &Shader) -> Result<Vec<GLuint>, LayoutError> {
+        let reflection = shader.reflection();
+        let active = reflection.attributes();
+
+        let locations = self
+            .attributes
+            .iter()
+            .map(|attribute| {
+                let found = active.iter().find(|active| match &attribute.location {
+                    AttributeLocation::Name(name) => active.name == *name,
+                    AttributeLocation::Index(index) => active.location == *index as GLint,
+                });
+                let Some(found) = found else {
+                    return Err(LayoutError::MissingAttribute(attribute.location.clone()));
+                };
+                if found.is_integer() != attribute.attribute_type.is_integer() {
+                    return Err(LayoutError::TypeMismatch {
+                        name: found.name.clone(),
+                        attribute_type: attribute.attribute_type,
+                    });
+                }
+                Ok(found.location as GLuint)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(unset) = active
+            .iter()
+            .find(|active| !locations.contains(&(active.location as GLuint)))
+        {
+            return Err(LayoutError::UnsetAttribute(unset.name.clone()));
+        }
+
+        Ok(locations)
+    }
+
+    /// Sets up the attribute formats and binding points of `vao`. The buffers themselves are
+    /// attached separately.
+    pub(super) fn apply(&self, gl: &Gl, vao: GLuint, shader: &Shader) -> Result<(), LayoutError> {
+        let locations = self.resolve(shader)?;
+
+        for (attribute, location) in self.attributes.iter().zip(locations) {
+            let attribute_type = attribute.attribute_type;
+            unsafe {
+                gl.EnableVertexArrayAttrib(vao, location);
+                if attribute_type.is_integer() {
+                    gl.VertexArrayAttribIFormat(
+                        vao,
+                        location,
+                        attribute.components as GLint,
+                        attribute_type.gl_type(),
+                        attribute.offset,
+                    );
+                } else {
+                    gl.VertexArrayAttribFormat(
+                        vao,
+                        location,
+                        attribute.components as GLint,
+                        attribute_type.gl_type(),
+                        attribute_type.normalized().into(),
+                        attribute.offset,
+                    );
+                }
+                gl.VertexArrayAttribBinding(vao, location, attribute.binding);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum LayoutError {
+    /// The layout has an attribute the shader doesn't (or that was optimized out).
+    MissingAttribute(AttributeLocation),
+    /// The shader reads an attribute the layout doesn't provide.
+    UnsetAttribute(String),
+    /// Integer data for a float attribute or the other way around.
+    TypeMismatch {
+        name: String,
+        attribute_type: AttributeType,
+    },
+    /// The layout reads from a binding no buffer was added for.
+    MissingBuffer(u32),
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayoutError::MissingAttribute(location) => {
+                write!(f, "Attribute not found in shader: {location}")
+            }
+            LayoutError::UnsetAttribute(name) => {
+                write!(
+                    f,
+                    "Shader attribute '{name}' isn't set by the vertex layout"
+                )
+            }
+            LayoutError::TypeMismatch {
+                name,
+                attribute_type,
+            } => write!(
+                f,
+                "Attribute '{name}' can't be read from {attribute_type:?} data"
+            ),
+            LayoutError::MissingBuffer(binding) => {
+                write!(f, "No buffer was added for binding {binding}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
@@ -1,2 +1,3 @@
 pub mod cube;
 pub mod light_cube;
+pub mod model;
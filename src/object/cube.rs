@@ -1,13 +1,19 @@
 use std::rc::Rc;
 
-use glam::{Mat4, Vec3};
+use glam::{Vec3, Vec4};
 
 use crate::{
     camera::direction::Degrees,
-    gl::{types::GLfloat, Gl},
-    mesh::{Mesh, VertexBuffer},
-    renderer::texture::TextureManager,
-    shader::{LightCasterShader, ShaderTrait},
+    gl::Gl,
+    mesh::{LayoutError, Mesh, VertexBuffer},
+    renderer::{
+        texture::{ColorSpace, Texture, TextureManager},
+        vertex_data_layout,
+    },
+    shader::{
+        DrawableShader, LightCasterShader, MaterialBlock, AMBIENT_OCCLUSION_UNIT, DIFFUSE_UNIT,
+        SPECULAR_UNIT,
+    },
 };
 
 const SHININESS_DEFAULT: f32 = 32.0;
@@ -16,87 +22,79 @@ pub struct Cube {
     mesh: Mesh,
     shader: Rc<LightCasterShader>,
     material: Material,
+    texture_manager: TextureManager,
+    /// White, the cube has no baked occlusion.
+    ambient_occlusion: Texture,
 }
 pub struct Material {
     pub shininess: f32,
 }
 
 impl Cube {
+    /// Fails if the shader doesn't read the vertex data's layout.
     pub fn new(
         gl: &Gl,
         pos: Vec3,
         shader: Rc<LightCasterShader>,
         vertex_data: &[f32],
         vertex_data_stride: i32,
-    ) -> Self {
-        let lit_object_vertex_buffer = VertexBuffer::new(gl, vertex_data, vertex_data_stride);
+    ) -> Result<Self, LayoutError> {
+        let mut lit_object_vertex_buffer = VertexBuffer::new(gl, vertex_data, vertex_data_stride);
 
-        lit_object_vertex_buffer.set_float_attribute_position(
-            gl,
-            "aPos",
-            shader.shader.get_id(),
-            0,
-            3,
-        );
-        lit_object_vertex_buffer.set_float_attribute_position(
-            gl,
-            "aNormal",
-            shader.shader.get_id(),
-            3,
-            3,
-        );
-        lit_object_vertex_buffer.set_float_attribute_position(
-            gl,
-            "aTexCoords",
-            shader.shader.get_id(),
-            6,
-            2,
-        );
+        lit_object_vertex_buffer.set_layout(gl, &vertex_data_layout(), &shader.shader)?;
 
         let mut texture_manager = TextureManager::new();
         texture_manager.create_texture(
             gl,
             "material.diffuse",
             "static/diffuse_container.png",
-            &shader.shader,
-            0,
+            ColorSpace::Srgb,
         );
         texture_manager.create_texture(
             gl,
             "material.specular",
             "static/specular_container.png",
-            &shader.shader,
-            1,
+            ColorSpace::Linear,
         );
-        texture_manager.bind_texture(gl, "material.diffuse", 0);
-        texture_manager.bind_texture(gl, "material.specular", 1);
-        Self {
+        let ambient_occlusion = texture_manager.load_color(gl, Vec4::ONE);
+        Ok(Self {
             mesh: Mesh::new(pos, lit_object_vertex_buffer),
             shader,
             material: Material {
                 shininess: SHININESS_DEFAULT,
             },
-        }
+            texture_manager,
+            ambient_occlusion,
+        })
     }
     pub fn adjust_blend(&mut self, blend: f32) {
         self.mesh.adjust_blend(blend)
     }
-    pub fn draw(&self, gl: &Gl, view_matrix: Mat4) {
+    pub fn draw(&self, gl: &Gl) {
         self.update_material_uniforms(gl);
-        self.mesh.draw(gl, view_matrix, self.shader.as_ref());
+        self.mesh.draw(gl, self.shader.as_ref());
     }
-    pub fn adjust_zoom(&mut self, zoom: GLfloat) {
-        self.mesh.adjust_zoom(zoom);
+    /// Draws only the cube's depth, for shadow maps.
+    pub fn draw_depth(&self, gl: &Gl, shader: &dyn DrawableShader) {
+        self.mesh.draw(gl, shader);
     }
     pub fn rotate_by(&mut self, rotation: Degrees) {
         self.mesh.rotate_by(rotation);
     }
 
     fn update_material_uniforms(&self, gl: &Gl) {
+        // Models bind their own textures to these units, so they have to be rebound each draw.
+        self.texture_manager
+            .bind_texture(gl, "material.diffuse", DIFFUSE_UNIT);
+        self.texture_manager
+            .bind_texture(gl, "material.specular", SPECULAR_UNIT);
+        unsafe {
+            gl.BindTextureUnit(AMBIENT_OCCLUSION_UNIT, self.ambient_occlusion.id);
+        }
         self.shader
-            .shader
-            .set_float(gl, "material.shininess", self.material.shininess)
-            .unwrap();
+            .material
+            .block
+            .set(MaterialBlock::new(self.material.shininess));
     }
 
     pub fn set_material(&mut self, material: Material) {
@@ -1,12 +1,12 @@
 use std::rc::Rc;
 
-use glam::{vec3, Mat4, Vec3};
+use glam::{vec3, Vec3};
 
 use crate::{
     camera::direction::Degrees,
-    gl::{types::GLfloat, Gl},
-    mesh::{Mesh, VertexBuffer},
-    shader::{LightCubeShader, ShaderTrait},
+    gl::Gl,
+    mesh::{AttributeType, Mesh, VertexBuffer, VertexLayout},
+    shader::{LightCubeShader, UniformGetSet},
 };
 
 pub struct LightCube {
@@ -15,17 +15,22 @@ pub struct LightCube {
 }
 
 impl LightCube {
-    pub fn new(gl: &Gl, pos: Vec3, vertex_data: &[f32], vertex_data_stride: i32) -> Self {
-        let shader = Rc::new(LightCubeShader::new(gl));
-        let lit_object_vertex_buffer = VertexBuffer::new(gl, vertex_data, vertex_data_stride);
+    pub fn new(
+        gl: &Gl,
+        pos: Vec3,
+        shader: Rc<LightCubeShader>,
+        vertex_data: &[f32],
+        vertex_data_stride: i32,
+    ) -> Self {
+        let mut lit_object_vertex_buffer = VertexBuffer::new(gl, vertex_data, vertex_data_stride);
 
-        lit_object_vertex_buffer.set_float_attribute_position(
-            gl,
-            "aPos",
-            shader.shader.get_id(),
-            0,
-            3,
-        );
+        // Light cubes share the lit cube's vertex data but only need the positions.
+        let layout = VertexLayout::new()
+            .attribute("aPos", 3, AttributeType::F32)
+            .padding(5 * size_of::<f32>() as u32);
+        lit_object_vertex_buffer
+            .set_layout(gl, &layout, &shader.shader)
+            .unwrap_or_else(|err| panic!("Light cube shader can't read the light cubes: {err}"));
 
         let mut mesh = Mesh::new(pos, lit_object_vertex_buffer);
         mesh.adjust_scale(vec3(0.2, 0.2, 0.2));
@@ -35,11 +40,10 @@ impl LightCube {
     pub fn adjust_blend(&mut self, blend: f32) {
         self.mesh.adjust_blend(blend)
     }
-    pub fn draw(&self, gl: &Gl, view_matrix: Mat4) {
-        self.mesh.draw(gl, view_matrix, self.shader.as_ref());
-    }
-    pub fn adjust_zoom(&mut self, zoom: GLfloat) {
-        self.mesh.adjust_zoom(zoom);
+    /// Draws the cube in `color`, which should be past the bloom threshold for it to glow.
+    pub fn draw(&self, gl: &Gl, color: Vec3) {
+        self.shader.light_color.set(color);
+        self.mesh.draw(gl, self.shader.as_ref());
     }
     pub fn rotate_by(&mut self, rotation: Degrees) {
         self.mesh.rotate_by(rotation);
@@ -0,0 +1,324 @@
+use std::{
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use glam::{Mat3, Mat4, Vec2, Vec3, Vec4};
+use image::{DynamicImage, RgbaImage};
+
+use crate::{
+    assimp::{self, ImportError, Node, PostProcess, Scene, TextureData, TextureType},
+    camera::direction::Degrees,
+    gl::Gl,
+    mesh::{Indices, LayoutError, Mesh, VertexBuffer},
+    renderer::{
+        texture::{ColorSpace, Texture, TextureManager},
+        vertex_data_layout,
+    },
+    shader::{
+        DrawableShader, LightCasterShader, MaterialBlock, AMBIENT_OCCLUSION_UNIT, DIFFUSE_UNIT,
+        SPECULAR_UNIT,
+    },
+};
+
+const SHININESS_DEFAULT: f32 = 32.0;
+const DIFFUSE_COLOR_DEFAULT: Vec4 = Vec4::ONE;
+const SPECULAR_COLOR_DEFAULT: Vec4 = Vec4::ZERO;
+
+/// Position, normal and texture coordinates, matching the layout of `VERTEX_DATA`.
+const MODEL_VERTEX_STRIDE: i32 = 8;
+
+pub struct Model {
+    meshes: Vec<ModelMesh>,
+    materials: Vec<ModelMaterial>,
+    shader: Rc<LightCasterShader>,
+}
+
+struct ModelMesh {
+    mesh: Mesh,
+    material_index: usize,
+}
+
+/// Textures bound to `material.diffuse`, `material.specular` and `material.ambientOcclusion`
+/// while drawing a mesh. Materials without a texture get a single texel one filled with the
+/// material's color instead, white for ambient occlusion.
+struct ModelMaterial {
+    diffuse: Texture,
+    specular: Texture,
+    ambient_occlusion: Texture,
+    shininess: f32,
+}
+
+impl Model {
+    pub fn new(
+        gl: &Gl,
+        path: &str,
+        pos: Vec3,
+        shader: Rc<LightCasterShader>,
+    ) -> Result<Self, ImportError> {
+        let flags = PostProcess::new()
+            .triangulate()
+            .gen_normals()
+            .join_vertices();
+        let scene = Scene::import(path, flags)?;
+
+        // Texture paths in model files are relative to the model itself.
+        let model_dir = Path::new(path).parent().unwrap_or(Path::new(""));
+        let mut texture_manager = TextureManager::new();
+        let materials = scene
+            .materials()
+            .map(|material| load_material(gl, &scene, material, model_dir, &mut texture_manager))
+            .collect();
+
+        let mut meshes = vec![];
+        if let Some(root) = scene.root_node() {
+            load_node(gl, &scene, root, Mat4::IDENTITY, pos, &shader, &mut meshes).map_err(
+                |err| {
+                    ImportError::new(
+                        path.into(),
+                        format!("Can't draw with the lit shader: {err}"),
+                    )
+                },
+            )?;
+        }
+
+        Ok(Self {
+            meshes,
+            materials,
+            shader,
+        })
+    }
+
+    pub fn draw(&self, gl: &Gl) {
+        for model_mesh in &self.meshes {
+            if let Some(material) = self.materials.get(model_mesh.material_index) {
+                self.bind_material(gl, material);
+            }
+            model_mesh.mesh.draw(gl, self.shader.as_ref());
+        }
+    }
+    /// Draws only the model's depth, for shadow maps.
+    pub fn draw_depth(&self, gl: &Gl, shader: &dyn DrawableShader) {
+        for model_mesh in &self.meshes {
+            model_mesh.mesh.draw(gl, shader);
+        }
+    }
+    pub fn rotate_by(&mut self, rotation: Degrees) {
+        for model_mesh in &mut self.meshes {
+            model_mesh.mesh.rotate_by(rotation);
+        }
+    }
+    pub fn set_pos(&mut self, pos: Vec3) {
+        for model_mesh in &mut self.meshes {
+            model_mesh.mesh.set_pos(pos);
+        }
+    }
+
+    fn bind_material(&self, gl: &Gl, material: &ModelMaterial) {
+        self.shader
+            .material
+            .block
+            .set(MaterialBlock::new(material.shininess));
+        unsafe {
+            gl.BindTextureUnit(DIFFUSE_UNIT, material.diffuse.id);
+            gl.BindTextureUnit(SPECULAR_UNIT, material.specular.id);
+            gl.BindTextureUnit(AMBIENT_OCCLUSION_UNIT, material.ambient_occlusion.id);
+        }
+    }
+}
+
+fn load_material(
+    gl: &Gl,
+    scene: &Scene,
+    material: assimp::Material<'_>,
+    model_dir: &Path,
+    texture_manager: &mut TextureManager,
+) -> ModelMaterial {
+    let diffuse_color = material.diffuse_color().unwrap_or(DIFFUSE_COLOR_DEFAULT);
+    let specular_color = material.specular_color().unwrap_or(SPECULAR_COLOR_DEFAULT);
+
+    let diffuse = load_material_texture(
+        gl,
+        scene,
+        material,
+        TextureType::Diffuse,
+        ColorSpace::Srgb,
+        model_dir,
+        texture_manager,
+    )
+    .unwrap_or_else(|| texture_manager.load_color(gl, diffuse_color));
+    let specular = load_material_texture(
+        gl,
+        scene,
+        material,
+        TextureType::Specular,
+        ColorSpace::Linear,
+        model_dir,
+        texture_manager,
+    )
+    .unwrap_or_else(|| texture_manager.load_color(gl, specular_color));
+    // OBJ files have no ambient occlusion map, so they're put in the ambient one instead.
+    let ambient_occlusion = [TextureType::AmbientOcclusion, TextureType::Ambient]
+        .into_iter()
+        .find_map(|texture_type| {
+            load_material_texture(
+                gl,
+                scene,
+                material,
+                texture_type,
+                ColorSpace::Linear,
+                model_dir,
+                texture_manager,
+            )
+        })
+        .unwrap_or_else(|| texture_manager.load_color(gl, Vec4::ONE));
+
+    ModelMaterial {
+        diffuse,
+        specular,
+        ambient_occlusion,
+        shininess: material
+            .shininess()
+            .filter(|shininess| *shininess > 0.0)
+            .unwrap_or(SHININESS_DEFAULT),
+    }
+}
+
+/// Loads the first texture of `texture_type`, logging and returning `None` if it can't be read.
+/// Color maps should be loaded as `ColorSpace::Srgb`, data like specular or normal maps as
+/// `ColorSpace::Linear`.
+fn load_material_texture(
+    gl: &Gl,
+    scene: &Scene,
+    material: assimp::Material<'_>,
+    texture_type: TextureType,
+    color_space: ColorSpace,
+    model_dir: &Path,
+    texture_manager: &mut TextureManager,
+) -> Option<Texture> {
+    let texture_path = material.texture_path(texture_type, 0)?;
+
+    if let Some(embedded) = scene.embedded_texture(&texture_path) {
+        let img = match embedded.data() {
+            TextureData::Compressed(bytes) => image::load_from_memory(bytes).ok(),
+            TextureData::Uncompressed {
+                width,
+                height,
+                texels,
+            } => {
+                let bgra_to_rgba = texels.iter().flat_map(|[b, g, r, a]| [*r, *g, *b, *a]);
+                RgbaImage::from_raw(width, height, bgra_to_rgba.collect())
+                    .map(DynamicImage::ImageRgba8)
+            }
+        };
+        let Some(img) = img else {
+            eprintln!("Failed to decode embedded texture '{texture_path}'");
+            return None;
+        };
+        return Some(texture_manager.load_image(gl, &texture_path, &img, color_space));
+    }
+
+    let path: PathBuf = model_dir.join(texture_path.replace('\\', "/"));
+    match texture_manager.load_texture(gl, &path, color_space) {
+        Ok(texture) => Some(texture),
+        Err(err) => {
+            eprintln!("Failed to load texture '{}': {err}", path.display());
+            None
+        }
+    }
+}
+
+/// Walks the node hierarchy depth first, baking each node's accumulated transform into the
+/// vertices of the meshes it references. Fails if the shader doesn't read the model's vertices.
+fn load_node(
+    gl: &Gl,
+    scene: &Scene,
+    node: Node<'_>,
+    parent_transform: Mat4,
+    pos: Vec3,
+    shader: &LightCasterShader,
+    meshes: &mut Vec<ModelMesh>,
+) -> Result<(), LayoutError> {
+    let transform = parent_transform * node.transformation();
+
+    for mesh_index in node.mesh_indices() {
+        let Some(scene_mesh) = scene.mesh(*mesh_index as usize) else {
+            continue;
+        };
+        let (vertex_data, indices) = build_vertex_data(scene_mesh, transform);
+        if indices.is_empty() {
+            continue;
+        }
+
+        let mut vertex_buffer = if scene_mesh.num_vertices() <= u16::MAX as usize + 1 {
+            let indices: Vec<u16> = indices.iter().map(|index| *index as u16).collect();
+            VertexBuffer::new_indexed(
+                gl,
+                &vertex_data,
+                MODEL_VERTEX_STRIDE,
+                Indices::U16(&indices),
+            )
+        } else {
+            VertexBuffer::new_indexed(
+                gl,
+                &vertex_data,
+                MODEL_VERTEX_STRIDE,
+                Indices::U32(&indices),
+            )
+        };
+        vertex_buffer.set_layout(gl, &vertex_data_layout(), &shader.shader)?;
+
+        meshes.push(ModelMesh {
+            mesh: Mesh::with_rotation(pos, 0.0, vertex_buffer),
+            material_index: scene_mesh.material_index(),
+        });
+    }
+
+    for child in node.children() {
+        load_node(gl, scene, child, transform, pos, shader, meshes)?;
+    }
+    Ok(())
+}
+
+/// Interleaves a scene mesh's vertices and collects the indices of its triangles. Points and
+/// lines are skipped.
+fn build_vertex_data(scene_mesh: assimp::Mesh<'_>, transform: Mat4) -> (Vec<f32>, Vec<u32>) {
+    let normal_matrix = Mat3::from_mat4(transform).inverse().transpose();
+
+    let mut normals = scene_mesh.normals();
+    let mut tex_coords = scene_mesh.tex_coords(0);
+
+    let mut vertex_data = Vec::with_capacity(scene_mesh.num_vertices() * 8);
+    for position in scene_mesh.positions() {
+        let position = transform.transform_point3(position);
+        let normal = normals
+            .as_mut()
+            .and_then(|normals| normals.next())
+            .map(|normal| (normal_matrix * normal).normalize_or_zero())
+            .unwrap_or(Vec3::ZERO);
+        let tex_coord = tex_coords
+            .as_mut()
+            .and_then(|tex_coords| tex_coords.next())
+            .unwrap_or(Vec2::ZERO);
+
+        vertex_data.extend_from_slice(&[
+            position.x,
+            position.y,
+            position.z,
+            normal.x,
+            normal.y,
+            normal.z,
+            tex_coord.x,
+            tex_coord.y,
+        ]);
+    }
+
+    let indices = scene_mesh
+        .faces()
+        .filter(|face| face.len() == 3)
+        .flatten()
+        .copied()
+        .collect();
+
+    (vertex_data, indices)
+}
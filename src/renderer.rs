@@ -1,4 +1,9 @@
+pub mod bloom;
+pub mod framebuffer;
+pub mod post_process;
+pub mod ssao;
 pub mod texture;
+pub mod tone_map;
 
 use std::{ffi::CString, rc::Rc};
 
@@ -7,24 +12,58 @@ use glutin::prelude::GlDisplay;
 use winit::keyboard::KeyCode;
 
 use crate::{
-    assimp::{aiImportFile, import_file},
-    camera::Camera,
-    gl::{self, types::GLfloat, Gl},
-    light::{DirectionLight, Light, PointLight, SpotLight},
+    camera::{Camera, CameraBlock},
+    gl::{
+        self,
+        types::{GLenum, GLfloat},
+        Gl,
+    },
+    light::{
+        DirectionLight, DirectionLights, Light, LightsBlock, PointLight, PointLights, SpotLight,
+        SpotLights,
+    },
     logging::setup_logging,
-    object::cube::Cube,
-    shader::LightCasterShader,
+    mesh::{AttributeType, VertexLayout},
+    object::{cube::Cube, model::Model},
+    shader::{
+        BloomDownsampleShader, BloomUpsampleShader, DepthShader, DrawableShader, GBufferShader,
+        LightCasterShader, LightCubeShader, LuminanceShader, PointDepthShader, PostProcessShader,
+        ShaderWatcher, SsaoBlurShader, SsaoShader, ToneMapShader, UniformBuffer,
+    },
+    shadow::{DirectionalShadow, PointShadows, ShadowSettings, SpotShadows},
     timer::Timer,
 };
 
+use self::{
+    bloom::{Bloom, BloomSettings},
+    framebuffer::{Attachment, Framebuffer, FramebufferError, FramebufferLayout},
+    post_process::{ColorLut, PostEffect, PostProcess},
+    ssao::{Ssao, SsaoSettings},
+    tone_map::{ToneMap, ToneMapSettings},
+};
+
 type PositionDelta2D = (f64, f64);
 
 pub struct Renderer {
-    flash_light: Box<dyn Light>,
-    dir_light: Box<dyn Light>,
-    point_lights: Vec<Box<dyn Light>>,
+    /// The flashlight following the camera is the first one.
+    spot_lights: SpotLights,
+    /// The sun and the moon, only one of them is enabled at a time.
+    dir_lights: DirectionLights,
+    point_lights: PointLights,
+    light_cube_program: Rc<LightCubeShader>,
+    dir_shadow: DirectionalShadow,
+    point_shadows: PointShadows,
+    spot_shadows: SpotShadows,
     lit_objects: Vec<Cube>,
+    models: Vec<Model>,
     camera: Camera,
+    camera_block: UniformBuffer<CameraBlock>,
+    lights_block: Rc<UniformBuffer<LightsBlock>>,
+    ssao: Ssao,
+    /// The scene is drawn multisampled into this, then resolved for `post_process`.
+    scene_target: Framebuffer,
+    post_process: PostProcess,
+    shader_watcher: ShaderWatcher,
     gl: Gl,
 }
 
@@ -35,47 +74,197 @@ impl Renderer {
             gl_display.get_proc_address(symbol.as_c_str()).cast()
         });
 
-        dbg!(*import_file("static/backpack/backpack.obj"));
-
-        unsafe { gl.Enable(gl::DEPTH_TEST) };
+        unsafe {
+            gl.Enable(gl::DEPTH_TEST);
+            // Lighting happens in linear space, writes into the window and sRGB targets are
+            // gamma encoded.
+            gl.Enable(gl::FRAMEBUFFER_SRGB);
+        }
         setup_logging(&gl);
+        if !default_framebuffer_is_srgb(&gl) {
+            eprintln!("The window isn't sRGB capable, colors will look too dark");
+        }
+
+        let lit_object_program =
+            Rc::new(LightCasterShader::new(&gl).unwrap_or_else(|err| panic!("{err}")));
+        let light_cube_program =
+            Rc::new(LightCubeShader::new(&gl).unwrap_or_else(|err| panic!("{err}")));
+
+        let mut shader_watcher = ShaderWatcher::new();
+        shader_watcher.watch(&lit_object_program.shader);
+        shader_watcher.watch(&light_cube_program.shader);
 
-        let lit_object_program = Rc::new(LightCasterShader::new(&gl));
+        let depth_program = Rc::new(DepthShader::new(&gl).unwrap_or_else(|err| panic!("{err}")));
+        shader_watcher.watch(&depth_program.shader);
+        let dir_shadow =
+            DirectionalShadow::new(&gl, ShadowSettings::default(), Rc::clone(&depth_program))
+                .unwrap_or_else(|err| panic!("{err}"));
+        let spot_shadows = SpotShadows::new(
+            &gl,
+            SPOT_SHADOW_ATLAS_RESOLUTION,
+            SPOT_SHADOW_TILE_RESOLUTION,
+            depth_program,
+        )
+        .unwrap_or_else(|err| panic!("{err}"));
+        let point_depth_program =
+            Rc::new(PointDepthShader::new(&gl).unwrap_or_else(|err| panic!("{err}")));
+        shader_watcher.watch(&point_depth_program.shader);
+        let point_shadows = PointShadows::new(&gl, POINT_SHADOW_RESOLUTION, point_depth_program)
+            .unwrap_or_else(|err| panic!("{err}"));
 
-        let flash_light = Box::new(SpotLight::new(&gl, Rc::clone(&lit_object_program)));
-        let dir_light = Box::new(DirectionLight::new(&gl, Rc::clone(&lit_object_program)));
-        let point_lights: Vec<Box<dyn Light>> = POINT_LIGHT_POSITIONS
-            .iter()
-            .enumerate()
-            .map(|(index, pos)| {
-                let mut light: Box<dyn Light> = Box::new(PointLight::new(
+        let mut viewport = [0; 4];
+        unsafe { gl.GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr()) };
+        let scene_target = Framebuffer::new(
+            &gl,
+            FramebufferLayout::new()
+                .color(Attachment::renderbuffer(gl::RGBA16F))
+                .color(Attachment::renderbuffer(gl::RGBA16F))
+                .depth(Attachment::renderbuffer(gl::DEPTH24_STENCIL8))
+                .samples(SCENE_SAMPLES),
+            viewport[2],
+            viewport[3],
+        )
+        .unwrap_or_else(|err| panic!("{err}"));
+        let gbuffer_program = GBufferShader::new(&gl).unwrap_or_else(|err| panic!("{err}"));
+        shader_watcher.watch(&gbuffer_program.shader);
+        let ssao_program = SsaoShader::new(&gl).unwrap_or_else(|err| panic!("{err}"));
+        shader_watcher.watch(&ssao_program.shader);
+        let ssao_blur_program = SsaoBlurShader::new(&gl).unwrap_or_else(|err| panic!("{err}"));
+        shader_watcher.watch(&ssao_blur_program.shader);
+        let ssao = Ssao::new(
+            &gl,
+            gbuffer_program,
+            ssao_program,
+            ssao_blur_program,
+            viewport[2],
+            viewport[3],
+        )
+        .unwrap_or_else(|err| panic!("{err}"));
+        let tone_map_program = ToneMapShader::new(&gl).unwrap_or_else(|err| panic!("{err}"));
+        shader_watcher.watch(&tone_map_program.shader);
+        let luminance_program = LuminanceShader::new(&gl).unwrap_or_else(|err| panic!("{err}"));
+        shader_watcher.watch(&luminance_program.shader);
+        let tone_map = ToneMap::new(
+            &gl,
+            tone_map_program,
+            luminance_program,
+            viewport[2],
+            viewport[3],
+        )
+        .unwrap_or_else(|err| panic!("{err}"));
+        let downsample_program =
+            BloomDownsampleShader::new(&gl).unwrap_or_else(|err| panic!("{err}"));
+        shader_watcher.watch(&downsample_program.shader);
+        let upsample_program = BloomUpsampleShader::new(&gl).unwrap_or_else(|err| panic!("{err}"));
+        shader_watcher.watch(&upsample_program.shader);
+        let bloom = Bloom::new(
+            &gl,
+            downsample_program,
+            upsample_program,
+            viewport[2],
+            viewport[3],
+        )
+        .unwrap_or_else(|err| panic!("{err}"));
+        let post_process_program =
+            PostProcessShader::new(&gl).unwrap_or_else(|err| panic!("{err}"));
+        shader_watcher.watch(&post_process_program.shader);
+        let mut post_process = PostProcess::new(
+            &gl,
+            post_process_program,
+            tone_map,
+            bloom,
+            viewport[2],
+            viewport[3],
+        )
+        .unwrap_or_else(|err| panic!("{err}"));
+        post_process.set_lut(
+            ColorLut::from_fn(&gl, COLOR_LUT_SIZE, warm_grade)
+                .inspect_err(|err| eprintln!("{err}"))
+                .ok(),
+        );
+
+        let camera = Camera::new();
+        let camera_block = UniformBuffer::new(&gl, camera.block(gl.get_aspect_ratio()));
+        let lights_block = Rc::new(UniformBuffer::new(&gl, LightsBlock::default()));
+
+        let mut spot_lights = SpotLights::new(&gl, Rc::clone(&lights_block));
+        spot_lights.add(SpotLight::new(camera.pos(), camera.get_forwards_dir()).with_shadow());
+        for (pos, color) in STAGE_LIGHTS {
+            spot_lights.add(
+                SpotLight::new(pos, vec3(0.0, 0.0, -6.0) - pos)
+                    .with_strength(Vec3::ZERO, color, color)
+                    .with_cutoff(8.0, 12.0)
+                    .with_shadow(),
+            );
+        }
+
+        let mut dir_lights = DirectionLights::new(&gl, Rc::clone(&lights_block));
+        dir_lights.add(DirectionLight::new(vec3(-0.2, -1.0, -0.3).normalize()).with_shadow());
+        dir_lights
+            .add(
+                DirectionLight::new(vec3(0.3, -1.0, 0.2).normalize())
+                    .with_strength(
+                        vec3(0.02, 0.02, 0.05),
+                        vec3(0.1, 0.1, 0.2),
+                        vec3(0.2, 0.2, 0.3),
+                    )
+                    .with_shadow(),
+            )
+            .set_enabled(false);
+
+        let mut point_lights = PointLights::new(&gl, Rc::clone(&lights_block));
+        for (pos, casts_shadow) in POINT_LIGHTS {
+            point_lights
+                .add(PointLight::new(&gl, pos, Rc::clone(&light_cube_program)))
+                .set_casts_shadow(casts_shadow);
+        }
+
+        let lit_objects = LIT_CUBE_POSITIONS
+            .into_iter()
+            .filter_map(|pos| {
+                Cube::new(
                     &gl,
+                    pos,
                     Rc::clone(&lit_object_program),
-                    index as u8,
-                ));
-                light.set_pos(&gl, *pos);
-                light
+                    &VERTEX_DATA,
+                    VERTEX_DATA_STRIDE,
+                )
+                .inspect_err(|err| eprintln!("Can't draw cubes with the lit shader: {err}"))
+                .ok()
             })
             .collect();
 
-        let lit_objects = Vec::from(LIT_CUBE_POSITIONS.map(|pos| {
-            Cube::new(
-                &gl,
-                pos,
-                Rc::clone(&lit_object_program),
-                &VERTEX_DATA,
-                VERTEX_DATA_STRIDE,
-            )
-        }));
+        let models = match Model::new(
+            &gl,
+            "static/backpack/backpack.obj",
+            vec3(0.0, 0.0, -6.0),
+            Rc::clone(&lit_object_program),
+        ) {
+            Ok(model) => vec![model],
+            Err(err) => {
+                eprintln!("{err}");
+                vec![]
+            }
+        };
 
-        let camera = Camera::new();
         Self {
-            flash_light,
-            dir_light,
+            spot_lights,
+            dir_lights,
             point_lights,
+            light_cube_program,
+            dir_shadow,
+            point_shadows,
+            spot_shadows,
             lit_objects,
+            models,
             gl,
             camera,
+            camera_block,
+            lights_block,
+            ssao,
+            scene_target,
+            post_process,
+            shader_watcher,
         }
     }
 
@@ -95,27 +284,165 @@ impl Renderer {
         })
     }
 
+    /// `L` adds a point light where the camera is, `Backspace` removes the newest one. `F`
+    /// toggles the flashlight, `N` switches between day and night, `C` tints everything by
+    /// the shadow cascade it's in and `O` toggles ambient occlusion.
+    pub fn handle_light_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::KeyL => {
+                let light = PointLight::new(
+                    &self.gl,
+                    self.camera.pos(),
+                    Rc::clone(&self.light_cube_program),
+                );
+                self.point_lights.add(light);
+            }
+            KeyCode::KeyF => {
+                if let Some(flash_light) = self.spot_lights.get_mut(0) {
+                    flash_light.set_enabled(!flash_light.enabled());
+                }
+            }
+            KeyCode::KeyC => {
+                let settings = self.shadow_settings();
+                if let Err(err) = self.set_shadow_settings(ShadowSettings {
+                    debug_cascades: !settings.debug_cascades,
+                    ..settings
+                }) {
+                    eprintln!("{err}");
+                }
+            }
+            KeyCode::KeyO => {
+                let settings = self.ssao_settings();
+                self.set_ssao_settings(SsaoSettings {
+                    enabled: !settings.enabled,
+                    ..settings
+                });
+            }
+            KeyCode::KeyN => {
+                for light in self.dir_lights.iter_mut() {
+                    light.set_enabled(!light.enabled());
+                }
+            }
+            KeyCode::Backspace if !self.point_lights.is_empty() => {
+                self.point_lights.remove(self.point_lights.len() - 1);
+            }
+            _ => (),
+        }
+    }
+
+    /// Number keys toggle post processing effects: `1` grayscale, `2` inversion, `3` sharpen,
+    /// `4` blur, `5` edge detection, `6` vignette and `7` color grading. Effects are applied
+    /// in the order they were turned on.
+    ///
+    /// `T` cycles through the tone mapping operators, `=` and `-` raise and lower the exposure
+    /// and `E` toggles auto exposure. `B` toggles bloom.
+    pub fn handle_post_process_key(&mut self, key: KeyCode) {
+        let settings = self.tone_map_settings();
+        match key {
+            KeyCode::KeyT => self.set_tone_map_settings(ToneMapSettings {
+                operator: settings.operator.next(),
+                ..settings
+            }),
+            KeyCode::Equal => self.set_tone_map_settings(ToneMapSettings {
+                exposure: settings.exposure * EXPOSURE_STEP,
+                ..settings
+            }),
+            KeyCode::Minus => self.set_tone_map_settings(ToneMapSettings {
+                exposure: settings.exposure / EXPOSURE_STEP,
+                ..settings
+            }),
+            KeyCode::KeyE => self.set_tone_map_settings(ToneMapSettings {
+                auto_exposure: !settings.auto_exposure,
+                ..settings
+            }),
+            KeyCode::KeyB => {
+                let bloom = self.bloom_settings();
+                self.set_bloom_settings(BloomSettings {
+                    enabled: !bloom.enabled,
+                    ..bloom
+                });
+            }
+            _ => (),
+        }
+
+        let effect = match key {
+            KeyCode::Digit1 => PostEffect::Grayscale,
+            KeyCode::Digit2 => PostEffect::Invert,
+            KeyCode::Digit3 => PostEffect::sharpen(),
+            KeyCode::Digit4 => PostEffect::blur(),
+            KeyCode::Digit5 => PostEffect::edge_detect(),
+            KeyCode::Digit6 => PostEffect::vignette(),
+            KeyCode::Digit7 => PostEffect::ColorGrade { strength: 1.0 },
+            _ => return,
+        };
+        self.post_process.toggle(effect);
+    }
+
+    pub fn tone_map_settings(&self) -> ToneMapSettings {
+        self.post_process.tone_map().settings()
+    }
+    pub fn set_tone_map_settings(&mut self, settings: ToneMapSettings) {
+        self.post_process.tone_map_mut().set_settings(settings);
+    }
+
+    pub fn bloom_settings(&self) -> BloomSettings {
+        self.post_process.bloom().settings()
+    }
+    pub fn set_bloom_settings(&mut self, settings: BloomSettings) {
+        self.post_process.bloom_mut().set_settings(settings);
+    }
+
+    pub fn ssao_settings(&self) -> SsaoSettings {
+        self.ssao.settings()
+    }
+    pub fn set_ssao_settings(&mut self, settings: SsaoSettings) {
+        self.ssao.set_settings(settings);
+    }
+
+    pub fn post_process(&self) -> &PostProcess {
+        &self.post_process
+    }
+    pub fn post_process_mut(&mut self) -> &mut PostProcess {
+        &mut self.post_process
+    }
+
     pub fn handle_mouse_input(&mut self, delta: PositionDelta2D) {
         self.camera.adjust_yaw(delta.0 as f32 / 10.0);
         self.camera.adjust_pitch(-(delta.1 as f32 / 10.0));
     }
 
     pub fn draw(&mut self, timer: &Timer) {
+        self.shader_watcher.poll(&self.gl);
         self.draw_with_clear_color(timer, 0.1, 0.1, 0.1, 0.9);
     }
 
     pub fn adjust_zoom(&mut self, degrees: GLfloat) {
-        self.flash_light.adjust_zoom(degrees);
-        for light in &mut self.point_lights {
-            light.adjust_zoom(degrees);
-        }
-        for mesh in &mut self.lit_objects {
-            mesh.adjust_zoom(degrees);
-        }
+        self.camera.adjust_zoom(degrees);
     }
 
-    pub fn resize(&self, width: i32, height: i32) {
+    pub fn shadow_settings(&self) -> ShadowSettings {
+        self.dir_shadow.settings()
+    }
+    /// Fails if the shadow maps can't be reallocated for a new resolution or cascade count.
+    pub fn set_shadow_settings(
+        &mut self,
+        settings: ShadowSettings,
+    ) -> Result<(), FramebufferError> {
+        self.dir_shadow.set_settings(settings)
+    }
+
+    /// Resizes the viewport and every offscreen render target along with the window. A target
+    /// failing to resize doesn't stop the others from being resized.
+    pub fn resize(&mut self, width: i32, height: i32) {
         unsafe { self.gl.Viewport(0, 0, width, height) }
+        let results = [
+            self.scene_target.resize(width, height),
+            self.ssao.resize(width, height),
+            self.post_process.resize(width, height),
+        ];
+        for err in results.into_iter().filter_map(Result::err) {
+            eprintln!("{err}");
+        }
     }
 
     fn draw_with_clear_color(
@@ -128,34 +455,125 @@ impl Renderer {
     ) {
         let gl = &self.gl;
         unsafe {
-            gl.ClearColor(red, green, blue, alpha);
-            gl.Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
-
-            self.flash_light
-                .set_pos(gl, self.camera.pos())
-                .set_dir(gl, self.camera.get_forwards_dir())
-                .draw(gl, self.camera.view_matrix());
-            self.dir_light
-                .set_dir(gl, vec3(0.0, -1.0, 0.0).normalize())
-                .draw(gl, self.camera.view_matrix());
-            self.point_lights
-                .iter()
-                .for_each(|light| light.draw(gl, self.camera.view_matrix()));
+            let aspect_ratio = gl.get_aspect_ratio();
+            self.camera_block.set(self.camera.block(aspect_ratio));
 
             for lit_object in &mut self.lit_objects {
                 lit_object.rotate_by(10.0 * timer.delta_time());
-                lit_object.draw(gl, self.camera.view_matrix())
             }
+            if let Some(flash_light) = self.spot_lights.get_mut(0) {
+                flash_light
+                    .set_pos(gl, self.camera.pos())
+                    .set_dir(gl, self.camera.get_forwards_dir());
+            }
+            // The SSAO passes use texture units the shadow maps are bound to, so they go first.
+            self.ssao.render(
+                self.camera.projection_matrix(aspect_ratio),
+                |gbuffer_shader| draw_geometry(gl, &self.lit_objects, &self.models, gbuffer_shader),
+            );
+            let shadow_light = self
+                .dir_lights
+                .iter()
+                .filter(|light| light.enabled())
+                .enumerate()
+                .find(|(_, light)| light.casts_shadow())
+                .map(|(index, light)| (index, light.dir()));
+            self.dir_shadow.render(
+                gl,
+                shadow_light,
+                &self.camera,
+                aspect_ratio,
+                |depth_shader| draw_geometry(gl, &self.lit_objects, &self.models, depth_shader),
+            );
+            self.point_shadows
+                .render(&mut self.point_lights, |depth_shader| {
+                    draw_geometry(gl, &self.lit_objects, &self.models, depth_shader)
+                });
+            self.spot_shadows
+                .render(&mut self.spot_lights, |depth_shader| {
+                    draw_geometry(gl, &self.lit_objects, &self.models, depth_shader)
+                });
+
+            self.spot_lights.upload();
+            self.dir_lights.upload();
+            self.point_lights.upload();
+            self.lights_block.flush();
+
+            self.scene_target.render(|| {
+                gl.ClearColor(red, green, blue, alpha);
+                gl.Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+                self.point_lights.draw(gl);
+                for lit_object in &self.lit_objects {
+                    lit_object.draw(gl)
+                }
+                for model in &self.models {
+                    model.draw(gl);
+                }
+            });
+            self.post_process.apply(&self.scene_target);
         }
     }
 }
 
+fn default_framebuffer_is_srgb(gl: &Gl) -> bool {
+    let mut encoding = 0;
+    unsafe {
+        gl.GetNamedFramebufferAttachmentParameteriv(
+            0,
+            gl::BACK_LEFT,
+            gl::FRAMEBUFFER_ATTACHMENT_COLOR_ENCODING,
+            &mut encoding,
+        );
+    }
+    encoding as GLenum == gl::SRGB
+}
+
+/// Half way between the original colors and sepia, slightly warmer still.
+fn warm_grade(color: Vec3) -> Vec3 {
+    let sepia = vec3(
+        color.dot(vec3(0.393, 0.769, 0.189)),
+        color.dot(vec3(0.349, 0.686, 0.168)),
+        color.dot(vec3(0.272, 0.534, 0.131)),
+    );
+    color.lerp(sepia, 0.5) * vec3(1.0, 1.0, 0.9)
+}
+
+/// Draws the geometry of everything lit with `shader` instead of its own, for shadow maps and
+/// the SSAO G-buffer.
+fn draw_geometry(gl: &Gl, lit_objects: &[Cube], models: &[Model], shader: &dyn DrawableShader) {
+    for lit_object in lit_objects {
+        lit_object.draw_depth(gl, shader);
+    }
+    for model in models {
+        model.draw_depth(gl, shader);
+    }
+}
+
+/// Samples per pixel the scene is drawn with.
+const SCENE_SAMPLES: u32 = 4;
+const COLOR_LUT_SIZE: u32 = 32;
+/// Factor each press of `=` or `-` changes the exposure by.
+const EXPOSURE_STEP: f32 = 1.25;
+const POINT_SHADOW_RESOLUTION: u32 = 1024;
+/// Enough for a flashlight and fifteen stage lights.
+const SPOT_SHADOW_ATLAS_RESOLUTION: u32 = 4096;
+const SPOT_SHADOW_TILE_RESOLUTION: u32 = 1024;
+
+/// Position of each point light and whether it casts shadows, which only the ones among the
+/// cubes are worth the cost of.
 #[rustfmt::skip]
-static POINT_LIGHT_POSITIONS: [Vec3; 4] = [
-    vec3( 0.7,  0.2,  2.0),
-    vec3( 2.3, -3.3, -4.0),
-    vec3(-4.0,  2.0, -12.0),
-    vec3( 0.0,  0.0, -3.0)
+static POINT_LIGHTS: [(Vec3, bool); 4] = [
+    (vec3( 0.7,  0.2,  2.0), true),
+    (vec3( 2.3, -3.3, -4.0), false),
+    (vec3(-4.0,  2.0, -12.0), false),
+    (vec3( 0.0,  0.0, -3.0), true)
+];
+
+/// Spotlights pointed at the backpack, with their colors.
+#[rustfmt::skip]
+static STAGE_LIGHTS: [(Vec3, Vec3); 2] = [
+    (vec3(-4.0, 4.0, -2.0), vec3(1.0, 0.3, 0.3)),
+    (vec3( 4.0, 4.0, -2.0), vec3(0.3, 0.3, 1.0))
 ];
 
 #[rustfmt::skip]
@@ -174,6 +592,14 @@ static LIT_CUBE_POSITIONS: [Vec3; 10] = [
 
 pub const VERTEX_DATA_STRIDE: i32 = 8;
 
+/// Layout of `VERTEX_DATA`, also used for loaded models.
+pub fn vertex_data_layout() -> VertexLayout {
+    VertexLayout::new()
+        .attribute("aPos", 3, AttributeType::F32)
+        .attribute("aNormal", 3, AttributeType::F32)
+        .attribute("aTexCoords", 2, AttributeType::F32)
+}
+
 #[rustfmt::skip]
 pub static VERTEX_DATA: [f32; 288] = [
      // positions      // normals        // texture coords
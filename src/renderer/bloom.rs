@@ -0,0 +1,162 @@
+use crate::{
+    gl::{self, types::GLuint, Gl},
+    shader::{
+        BloomDownsampleShader, BloomUpsampleShader, BufferBlock, ShaderTrait, Std140, TextureUnit,
+        UniformBuffer, UniformGetSet, BLOOM_BINDING,
+    },
+};
+
+use super::framebuffer::{Attachment, Framebuffer, FramebufferError, FramebufferLayout};
+
+const THRESHOLD_DEFAULT: f32 = 1.0;
+const INTENSITY_DEFAULT: f32 = 0.04;
+/// Texture coordinate offset of the upsampling filter's taps.
+const FILTER_RADIUS: f32 = 0.005;
+/// Most times the bright fragments are halved in size, each time spreading the glow further.
+const MAX_MIPS: usize = 6;
+
+const SOURCE_UNIT: GLuint = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BloomSettings {
+    pub enabled: bool,
+    /// Luminance past which fragments start to glow.
+    pub threshold: f32,
+    /// How much of the blurred bright fragments is added back onto the scene.
+    pub intensity: f32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold: THRESHOLD_DEFAULT,
+            intensity: INTENSITY_DEFAULT,
+        }
+    }
+}
+
+/// The `BloomBlock` uniform block, read by every program drawing into the scene, see
+/// `BloomColor` in bloom.glsl.
+#[derive(Clone, Copy, Std140, BufferBlock)]
+#[repr(C)]
+#[block(binding = BLOOM_BINDING)]
+pub struct BloomBlock {
+    #[uniform(rename = "bloomThreshold")]
+    pub threshold: f32,
+    #[uniform(skip)]
+    _padding: [f32; 3],
+}
+
+impl BloomBlock {
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+/// Blurs the scene's bright fragments by downsampling them into smaller and smaller textures,
+/// then upsampling them back while adding each level onto the next larger one.
+pub struct Bloom {
+    gl: Gl,
+    downsample_shader: BloomDownsampleShader,
+    upsample_shader: BloomUpsampleShader,
+    settings: BloomSettings,
+    block: UniformBuffer<BloomBlock>,
+    /// Half the size of the scene, then a quarter and so on.
+    mips: Vec<Framebuffer>,
+}
+
+impl Bloom {
+    pub fn new(
+        gl: &Gl,
+        downsample_shader: BloomDownsampleShader,
+        upsample_shader: BloomUpsampleShader,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, FramebufferError> {
+        downsample_shader.source.set(TextureUnit(SOURCE_UNIT));
+        upsample_shader.source.set(TextureUnit(SOURCE_UNIT));
+        upsample_shader.filter_radius.set(FILTER_RADIUS);
+
+        let settings = BloomSettings::default();
+        let mut bloom = Self {
+            gl: gl.clone(),
+            downsample_shader,
+            upsample_shader,
+            settings,
+            block: UniformBuffer::new(gl, BloomBlock::new(settings.threshold)),
+            mips: vec![],
+        };
+        bloom.resize(width, height)?;
+        Ok(bloom)
+    }
+
+    pub fn settings(&self) -> BloomSettings {
+        self.settings
+    }
+    pub fn set_settings(&mut self, settings: BloomSettings) {
+        self.settings = settings;
+        self.block.set(BloomBlock::new(settings.threshold));
+    }
+
+    /// The blurred bright fragments, valid after `render`.
+    pub fn texture(&self) -> GLuint {
+        self.mips
+            .first()
+            .and_then(|mip| mip.color_texture(0))
+            .unwrap_or(0)
+    }
+
+    pub fn resize(&mut self, width: i32, height: i32) -> Result<(), FramebufferError> {
+        let (mut width, mut height) = (width, height);
+        self.mips.clear();
+        while self.mips.len() < MAX_MIPS && width > 1 && height > 1 {
+            (width, height) = (width / 2, height / 2);
+            self.mips.push(Framebuffer::new(
+                &self.gl,
+                FramebufferLayout::new().color(Attachment::texture(gl::R11F_G11F_B10F)),
+                width,
+                height,
+            )?);
+        }
+        Ok(())
+    }
+
+    /// Blurs `bright`, the texture holding the scene's bright fragments. The full screen
+    /// triangle's vertex array needs to be bound.
+    pub fn render(&self, bright: GLuint) {
+        if !self.settings.enabled {
+            return;
+        }
+        let gl = &self.gl;
+
+        self.downsample_shader.shader.enable(gl);
+        let mut source = bright;
+        for mip in &self.mips {
+            mip.render(|| unsafe {
+                gl.BindTextureUnit(SOURCE_UNIT, source);
+                gl.DrawArrays(gl::TRIANGLES, 0, 3);
+            });
+            source = mip.color_texture(0).unwrap_or(0);
+        }
+
+        self.upsample_shader.shader.enable(gl);
+        unsafe {
+            gl.Enable(gl::BLEND);
+            gl.BlendFunc(gl::ONE, gl::ONE);
+        }
+        for pair in self.mips.windows(2).rev() {
+            let [larger, smaller] = pair else {
+                unreachable!()
+            };
+            larger.render(|| unsafe {
+                gl.BindTextureUnit(SOURCE_UNIT, smaller.color_texture(0).unwrap_or(0));
+                gl.DrawArrays(gl::TRIANGLES, 0, 3);
+            });
+        }
+        unsafe { gl.Disable(gl::BLEND) };
+    }
+}
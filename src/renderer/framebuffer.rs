@@ -0,0 +1,500 @@
+use std::fmt;
+
+use crate::gl::{
+    self,
+    types::{GLbitfield, GLenum, GLint, GLuint},
+    Gl,
+};
+
+/// What an attachment's image is stored in. Textures can be sampled afterwards, renderbuffers
+/// can only be blitted from but may be faster to render into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttachmentKind {
+    Texture,
+    Renderbuffer,
+}
+
+/// An image of a framebuffer, in one of the sized internal formats like `gl::RGBA8` or
+/// `gl::DEPTH24_STENCIL8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attachment {
+    pub format: GLenum,
+    pub kind: AttachmentKind,
+    /// Whether a texture has a full mip chain, filled in with `Framebuffer::generate_mipmaps`.
+    pub mipmaps: bool,
+}
+
+impl Attachment {
+    pub fn texture(format: GLenum) -> Self {
+        Self {
+            format,
+            kind: AttachmentKind::Texture,
+            mipmaps: false,
+        }
+    }
+    pub fn renderbuffer(format: GLenum) -> Self {
+        Self {
+            format,
+            kind: AttachmentKind::Renderbuffer,
+            mipmaps: false,
+        }
+    }
+
+    /// A texture with a full mip chain, which multisampled textures can't have.
+    pub fn mipmapped_texture(format: GLenum) -> Self {
+        Self {
+            mipmaps: true,
+            ..Self::texture(format)
+        }
+    }
+
+    /// Formats holding both depth and stencil are attached to both attachment points at once.
+    fn has_stencil(self) -> bool {
+        matches!(self.format, gl::DEPTH24_STENCIL8 | gl::DEPTH32F_STENCIL8)
+    }
+}
+
+/// The attachments of a `Framebuffer` and how many samples they have.
+/// ```
+/// use learn_ogl_rs::{
+///     gl,
+///     renderer::framebuffer::{Attachment, FramebufferLayout},
+/// };
+///
+/// // Two color targets that can be sampled and a depth buffer that can't, 4x multisampled.
+/// let layout = FramebufferLayout::new()
+///     .color(Attachment::texture(gl::RGBA16F))
+///     .color(Attachment::texture(gl::RGBA8))
+///     .depth(Attachment::renderbuffer(gl::DEPTH24_STENCIL8))
+///     .samples(4);
+///
+/// assert_eq!(layout.colors().len(), 2);
+/// assert!(layout.is_multisampled());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FramebufferLayout {
+    colors: Vec<Attachment>,
+    depth: Option<Attachment>,
+    stencil: Option<Attachment>,
+    samples: u32,
+}
+
+impl FramebufferLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a color attachment, written by the fragment shader output at the next location.
+    pub fn color(mut self, attachment: Attachment) -> Self {
+        self.colors.push(attachment);
+        self
+    }
+
+    /// Sets the depth attachment. Combined depth and stencil formats are used as the stencil
+    /// attachment too.
+    pub fn depth(mut self, attachment: Attachment) -> Self {
+        self.depth = Some(attachment);
+        self
+    }
+
+    /// Sets a stencil attachment separate from the depth one.
+    pub fn stencil(mut self, attachment: Attachment) -> Self {
+        self.stencil = Some(attachment);
+        self
+    }
+
+    /// Samples per pixel of every attachment, 0 or 1 for no multisampling.
+    pub fn samples(mut self, samples: u32) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    pub fn colors(&self) -> &[Attachment] {
+        &self.colors
+    }
+
+    pub fn is_multisampled(&self) -> bool {
+        self.samples > 1
+    }
+
+    /// Attachment points and formats of every attachment, colors first.
+    fn attachment_points(&self) -> Vec<(GLenum, Attachment)> {
+        let mut points: Vec<_> = self
+            .colors
+            .iter()
+            .enumerate()
+            .map(|(index, color)| (gl::COLOR_ATTACHMENT0 + index as GLenum, *color))
+            .collect();
+        if let Some(depth) = self.depth {
+            let point = if depth.has_stencil() {
+                gl::DEPTH_STENCIL_ATTACHMENT
+            } else {
+                gl::DEPTH_ATTACHMENT
+            };
+            points.push((point, depth));
+        }
+        if let Some(stencil) = self.stencil {
+            points.push((gl::STENCIL_ATTACHMENT, stencil));
+        }
+        points
+    }
+
+    /// The buffers of the `glBlitFramebuffer` mask this layout has images for.
+    fn buffer_mask(&self) -> GLbitfield {
+        let mut mask = 0;
+        if !self.colors.is_empty() {
+            mask |= gl::COLOR_BUFFER_BIT;
+        }
+        if self.depth.is_some() {
+            mask |= gl::DEPTH_BUFFER_BIT;
+        }
+        if self.stencil.is_some() || self.depth.is_some_and(Attachment::has_stencil) {
+            mask |= gl::STENCIL_BUFFER_BIT;
+        }
+        mask
+    }
+}
+
+#[derive(Debug)]
+pub enum FramebufferError {
+    /// `glCheckFramebufferStatus` didn't return `GL_FRAMEBUFFER_COMPLETE`.
+    Incomplete(GLenum),
+    TooManyColorAttachments {
+        requested: usize,
+        max: GLint,
+    },
+    TooManySamples {
+        requested: u32,
+        max: GLint,
+    },
+}
+
+impl fmt::Display for FramebufferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FramebufferError::Incomplete(status) => {
+                let status = match *status {
+                    gl::FRAMEBUFFER_UNDEFINED => "GL_FRAMEBUFFER_UNDEFINED",
+                    gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT => "GL_FRAMEBUFFER_INCOMPLETE_ATTACHMENT",
+                    gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => {
+                        "GL_FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT"
+                    }
+                    gl::FRAMEBUFFER_UNSUPPORTED => "GL_FRAMEBUFFER_UNSUPPORTED",
+                    gl::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE => {
+                        "GL_FRAMEBUFFER_INCOMPLETE_MULTISAMPLE"
+                    }
+                    gl::FRAMEBUFFER_INCOMPLETE_LAYER_TARGETS => {
+                        "GL_FRAMEBUFFER_INCOMPLETE_LAYER_TARGETS"
+                    }
+                    _ => "an unknown status",
+                };
+                write!(f, "Framebuffer is incomplete: {status}")
+            }
+            FramebufferError::TooManyColorAttachments { requested, max } => write!(
+                f,
+                "Framebuffer has {requested} color attachments, the driver supports {max}"
+            ),
+            FramebufferError::TooManySamples { requested, max } => write!(
+                f,
+                "Framebuffer has {requested} samples, the driver supports {max}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FramebufferError {}
+
+/// A texture or renderbuffer owned by a `Framebuffer`.
+#[derive(Debug, Clone, Copy)]
+struct Image {
+    kind: AttachmentKind,
+    id: GLuint,
+}
+
+/// A framebuffer object and the images attached to it, all of the same size. Resizing
+/// reallocates every image.
+pub struct Framebuffer {
+    gl: Gl,
+    id: GLuint,
+    layout: FramebufferLayout,
+    /// Images in the order of `FramebufferLayout::attachment_points`.
+    images: Vec<Image>,
+    width: i32,
+    height: i32,
+}
+
+impl Framebuffer {
+    pub fn new(
+        gl: &Gl,
+        layout: FramebufferLayout,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, FramebufferError> {
+        let (mut max_color_attachments, mut max_draw_buffers, mut max_samples) = (0, 0, 0);
+        unsafe {
+            gl.GetIntegerv(gl::MAX_COLOR_ATTACHMENTS, &mut max_color_attachments);
+            gl.GetIntegerv(gl::MAX_DRAW_BUFFERS, &mut max_draw_buffers);
+            gl.GetIntegerv(gl::MAX_SAMPLES, &mut max_samples);
+        }
+        let max = max_color_attachments.min(max_draw_buffers);
+        if layout.colors.len() > max as usize {
+            return Err(FramebufferError::TooManyColorAttachments {
+                requested: layout.colors.len(),
+                max,
+            });
+        }
+        if layout.samples > max_samples as u32 {
+            return Err(FramebufferError::TooManySamples {
+                requested: layout.samples,
+                max: max_samples,
+            });
+        }
+
+        let mut framebuffer = Self {
+            gl: gl.clone(),
+            id: 0,
+            layout,
+            images: vec![],
+            width: 0,
+            height: 0,
+        };
+        framebuffer.allocate(width.max(1), height.max(1))?;
+        Ok(framebuffer)
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+    pub fn layout(&self) -> &FramebufferLayout {
+        &self.layout
+    }
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// The texture of the color attachment at `index`, `None` if it's a renderbuffer or there's
+    /// no such attachment.
+    pub fn color_texture(&self, index: usize) -> Option<GLuint> {
+        self.layout
+            .colors
+            .get(index)
+            .and_then(|_| self.texture(index))
+    }
+
+    /// The texture of the depth attachment, `None` if it's a renderbuffer or there's none.
+    pub fn depth_texture(&self) -> Option<GLuint> {
+        self.layout
+            .depth
+            .and_then(|_| self.texture(self.layout.colors.len()))
+    }
+
+    /// Fills in the mip chain of every texture that has one from what was rendered into it.
+    pub fn generate_mipmaps(&self) {
+        let attachments = self.layout.attachment_points();
+        for ((_, attachment), image) in attachments.iter().zip(&self.images) {
+            if attachment.mipmaps && image.kind == AttachmentKind::Texture {
+                unsafe { self.gl.GenerateTextureMipmap(image.id) };
+            }
+        }
+    }
+
+    /// Reallocates every image if the size changed, discarding their contents. Sizes are at
+    /// least one pixel, so minimized windows don't leave the framebuffer incomplete.
+    pub fn resize(&mut self, width: i32, height: i32) -> Result<(), FramebufferError> {
+        let (width, height) = (width.max(1), height.max(1));
+        if (width, height) == (self.width, self.height) {
+            return Ok(());
+        }
+        self.delete();
+        self.allocate(width, height)
+    }
+
+    /// Runs `draw` with the framebuffer as the render target and the viewport covering it,
+    /// then restores the framebuffer and viewport that were set before. Nothing is cleared.
+    pub fn render(&self, draw: impl FnOnce()) {
+        let gl = &self.gl;
+        let mut viewport = [0; 4];
+        let mut previous = 0;
+        unsafe {
+            gl.GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+            gl.GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut previous);
+            gl.BindFramebuffer(gl::FRAMEBUFFER, self.id);
+            gl.Viewport(0, 0, self.width, self.height);
+        }
+        draw();
+        unsafe {
+            gl.BindFramebuffer(gl::FRAMEBUFFER, previous as GLuint);
+            gl.Viewport(viewport[0], viewport[1], viewport[2], viewport[3]);
+        }
+    }
+
+    /// Copies the `mask` buffers into `target`, scaling them to its size. Only color can be
+    /// scaled smoothly, depth and stencil are always copied from the nearest pixel.
+    pub fn blit(&self, target: &Framebuffer, mask: GLbitfield) {
+        self.blit_to(target.id, target.width, target.height, mask);
+    }
+
+    /// Like `blit`, into the default framebuffer of size `width` by `height`.
+    pub fn blit_to_default(&self, width: i32, height: i32, mask: GLbitfield) {
+        self.blit_to(0, width, height, mask);
+    }
+
+    /// Resolves a multisampled framebuffer into `target`, which needs to be the same size.
+    /// Every color attachment is copied into the one at the same index of `target`, along with
+    /// depth and stencil if both have them.
+    pub fn resolve(&self, target: &Framebuffer) {
+        let gl = &self.gl;
+        let colors = self.layout.colors.len().min(target.layout.colors.len());
+        unsafe {
+            for index in 0..colors {
+                let attachment = gl::COLOR_ATTACHMENT0 + index as GLenum;
+                gl.NamedFramebufferReadBuffer(self.id, attachment);
+                gl.NamedFramebufferDrawBuffer(target.id, attachment);
+                self.blit_to(target.id, target.width, target.height, gl::COLOR_BUFFER_BIT);
+            }
+            self.reset_buffers();
+            target.reset_buffers();
+        }
+        let mask = self.layout.buffer_mask() & target.layout.buffer_mask() & !gl::COLOR_BUFFER_BIT;
+        if mask != 0 {
+            self.blit_to(target.id, target.width, target.height, mask);
+        }
+    }
+
+    fn blit_to(&self, target: GLuint, width: i32, height: i32, mask: GLbitfield) {
+        let scaled = (width, height) != (self.width, self.height);
+        let filter = if scaled && mask == gl::COLOR_BUFFER_BIT {
+            gl::LINEAR
+        } else {
+            gl::NEAREST
+        };
+        unsafe {
+            self.gl.BlitNamedFramebuffer(
+                self.id,
+                target,
+                0,
+                0,
+                self.width,
+                self.height,
+                0,
+                0,
+                width,
+                height,
+                mask,
+                filter,
+            );
+        }
+    }
+
+    fn texture(&self, image: usize) -> Option<GLuint> {
+        self.images
+            .get(image)
+            .filter(|image| image.kind == AttachmentKind::Texture)
+            .map(|image| image.id)
+    }
+
+    /// Draws into every color attachment and reads from the first, or neither without any.
+    unsafe fn reset_buffers(&self) {
+        let gl = &self.gl;
+        if self.layout.colors.is_empty() {
+            gl.NamedFramebufferDrawBuffer(self.id, gl::NONE);
+            gl.NamedFramebufferReadBuffer(self.id, gl::NONE);
+            return;
+        }
+        let draw_buffers: Vec<GLenum> = (0..self.layout.colors.len())
+            .map(|index| gl::COLOR_ATTACHMENT0 + index as GLenum)
+            .collect();
+        gl.NamedFramebufferDrawBuffers(self.id, draw_buffers.len() as i32, draw_buffers.as_ptr());
+        gl.NamedFramebufferReadBuffer(self.id, gl::COLOR_ATTACHMENT0);
+    }
+
+    fn allocate(&mut self, width: i32, height: i32) -> Result<(), FramebufferError> {
+        let gl = &self.gl;
+        let samples = self.layout.samples as i32;
+        let multisampled = self.layout.is_multisampled();
+        let mut images = vec![];
+        unsafe {
+            gl.CreateFramebuffers(1, &mut self.id);
+            for (point, attachment) in self.layout.attachment_points() {
+                let mut id = 0;
+                match attachment.kind {
+                    AttachmentKind::Texture if multisampled => {
+                        gl.CreateTextures(gl::TEXTURE_2D_MULTISAMPLE, 1, &mut id);
+                        // Fixed sample locations let textures share a framebuffer with
+                        // renderbuffers.
+                        gl.TextureStorage2DMultisample(
+                            id,
+                            samples,
+                            attachment.format,
+                            width,
+                            height,
+                            gl::TRUE,
+                        );
+                        gl.NamedFramebufferTexture(self.id, point, id, 0);
+                    }
+                    AttachmentKind::Texture => {
+                        let (levels, min_filter) = if attachment.mipmaps {
+                            let levels = 32 - width.max(height).leading_zeros();
+                            (levels as i32, gl::LINEAR_MIPMAP_NEAREST)
+                        } else {
+                            (1, gl::LINEAR)
+                        };
+                        gl.CreateTextures(gl::TEXTURE_2D, 1, &mut id);
+                        gl.TextureStorage2D(id, levels, attachment.format, width, height);
+                        gl.TextureParameteri(id, gl::TEXTURE_MIN_FILTER, min_filter as i32);
+                        gl.TextureParameteri(id, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+                        gl.TextureParameteri(id, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+                        gl.TextureParameteri(id, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+                        gl.NamedFramebufferTexture(self.id, point, id, 0);
+                    }
+                    AttachmentKind::Renderbuffer => {
+                        gl.CreateRenderbuffers(1, &mut id);
+                        gl.NamedRenderbufferStorageMultisample(
+                            id,
+                            if multisampled { samples } else { 0 },
+                            attachment.format,
+                            width,
+                            height,
+                        );
+                        gl.NamedFramebufferRenderbuffer(self.id, point, gl::RENDERBUFFER, id);
+                    }
+                }
+                images.push(Image {
+                    kind: attachment.kind,
+                    id,
+                });
+            }
+            self.images = images;
+            self.width = width;
+            self.height = height;
+            self.reset_buffers();
+
+            match gl.CheckNamedFramebufferStatus(self.id, gl::FRAMEBUFFER) {
+                gl::FRAMEBUFFER_COMPLETE => Ok(()),
+                status => Err(FramebufferError::Incomplete(status)),
+            }
+        }
+    }
+
+    fn delete(&mut self) {
+        let gl = &self.gl;
+        unsafe {
+            gl.DeleteFramebuffers(1, &self.id);
+            for image in self.images.drain(..) {
+                match image.kind {
+                    AttachmentKind::Texture => gl.DeleteTextures(1, &image.id),
+                    AttachmentKind::Renderbuffer => gl.DeleteRenderbuffers(1, &image.id),
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        self.delete();
+    }
+}
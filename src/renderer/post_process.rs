@@ -0,0 +1,379 @@
+use std::path::Path;
+
+use glam::{vec3, Mat3, Vec3};
+use image::{
+    error::{ParameterError, ParameterErrorKind},
+    ImageError, ImageReader,
+};
+
+use crate::{
+    gl::{self, types::GLuint, Gl},
+    shader::{PostProcessShader, ShaderTrait, TextureUnit, UniformGetSet},
+};
+
+use super::{
+    bloom::Bloom,
+    framebuffer::{Attachment, Framebuffer, FramebufferError, FramebufferLayout},
+    tone_map::ToneMap,
+};
+
+// Values of the post process shader's `effect` uniform.
+const EFFECT_GRAYSCALE: i32 = 0;
+const EFFECT_INVERT: i32 = 1;
+const EFFECT_KERNEL: i32 = 2;
+const EFFECT_VIGNETTE: i32 = 3;
+const EFFECT_COLOR_GRADE: i32 = 4;
+
+/// The effect values, defined in the post process shader under these names.
+pub const EFFECT_DEFINES: [(&str, i32); 5] = [
+    ("EFFECT_GRAYSCALE", EFFECT_GRAYSCALE),
+    ("EFFECT_INVERT", EFFECT_INVERT),
+    ("EFFECT_KERNEL", EFFECT_KERNEL),
+    ("EFFECT_VIGNETTE", EFFECT_VIGNETTE),
+    ("EFFECT_COLOR_GRADE", EFFECT_COLOR_GRADE),
+];
+
+// Texture units the post process shader samples from.
+const SCREEN_UNIT: GLuint = 0;
+const LUT_UNIT: GLuint = 1;
+
+/// One full screen pass of a `PostProcess`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PostEffect {
+    Grayscale,
+    Invert,
+    /// Replaces every pixel with its 3x3 neighbourhood weighted by the matrix, the column being
+    /// the horizontal offset. See `PostEffect::sharpen` and friends.
+    Kernel(Mat3),
+    /// Darkens the screen further than `radius` from its center, fading in over `softness` and
+    /// reaching `strength` at the darkest. Distances are in texture coordinates.
+    Vignette {
+        radius: f32,
+        softness: f32,
+        strength: f32,
+    },
+    /// Maps colors through the `PostProcess`'s LUT, mixed with the original colors by
+    /// `strength`. Skipped until a LUT is set.
+    ColorGrade {
+        strength: f32,
+    },
+}
+
+impl PostEffect {
+    pub fn sharpen() -> Self {
+        PostEffect::Kernel(Mat3::from_cols_array(&[
+            -1.0, -1.0, -1.0, //
+            -1.0, 9.0, -1.0, //
+            -1.0, -1.0, -1.0,
+        ]))
+    }
+    pub fn blur() -> Self {
+        PostEffect::Kernel(
+            Mat3::from_cols_array(&[
+                1.0, 2.0, 1.0, //
+                2.0, 4.0, 2.0, //
+                1.0, 2.0, 1.0,
+            ]) / 16.0,
+        )
+    }
+    pub fn edge_detect() -> Self {
+        PostEffect::Kernel(Mat3::from_cols_array(&[
+            1.0, 1.0, 1.0, //
+            1.0, -8.0, 1.0, //
+            1.0, 1.0, 1.0,
+        ]))
+    }
+    pub fn vignette() -> Self {
+        PostEffect::Vignette {
+            radius: 0.4,
+            softness: 0.4,
+            strength: 0.8,
+        }
+    }
+
+    fn id(&self) -> i32 {
+        match self {
+            PostEffect::Grayscale => EFFECT_GRAYSCALE,
+            PostEffect::Invert => EFFECT_INVERT,
+            PostEffect::Kernel(_) => EFFECT_KERNEL,
+            PostEffect::Vignette { .. } => EFFECT_VIGNETTE,
+            PostEffect::ColorGrade { .. } => EFFECT_COLOR_GRADE,
+        }
+    }
+}
+
+/// A color grading lookup table, a 3D texture indexed by the red, green and blue of the color
+/// to replace.
+pub struct ColorLut {
+    gl: Gl,
+    texture: GLuint,
+    size: u32,
+}
+
+impl ColorLut {
+    /// Smallest table that still holds both black and white.
+    pub const MIN_SIZE: u32 = 2;
+
+    /// Builds a `size`³ table by running `grade` on colors evenly spread between black and
+    /// white. Fails if `size` is below `MIN_SIZE`.
+    pub fn from_fn(gl: &Gl, size: u32, grade: impl Fn(Vec3) -> Vec3) -> Result<Self, ImageError> {
+        check_lut_size(size)?;
+        let max = (size - 1) as f32;
+        let mut data = Vec::with_capacity((size * size * size * 3) as usize);
+        for blue in 0..size {
+            for green in 0..size {
+                for red in 0..size {
+                    let color = grade(vec3(red as f32, green as f32, blue as f32) / max);
+                    let color = (color.clamp(Vec3::ZERO, Vec3::ONE) * 255.0).round();
+                    data.extend([color.x as u8, color.y as u8, color.z as u8]);
+                }
+            }
+        }
+        Ok(Self::upload(gl, size, &data))
+    }
+
+    /// Loads a table laid out as a horizontal strip of `size` squares of `size`² pixels, one
+    /// per blue value. Red increases to the right and green downward within each square.
+    pub fn load(gl: &Gl, path: &Path) -> Result<Self, ImageError> {
+        let img = ImageReader::open(path)?.decode()?.to_rgb8();
+        let size = img.height();
+        check_lut_size(size)?;
+        if img.width() != size * size {
+            return Err(ImageError::Parameter(ParameterError::from_kind(
+                ParameterErrorKind::DimensionMismatch,
+            )));
+        }
+        let mut data = Vec::with_capacity((size * size * size * 3) as usize);
+        for blue in 0..size {
+            for green in 0..size {
+                for red in 0..size {
+                    data.extend(img.get_pixel(blue * size + red, green).0);
+                }
+            }
+        }
+        Ok(Self::upload(gl, size, &data))
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    fn upload(gl: &Gl, size: u32, data: &[u8]) -> Self {
+        let mut texture = 0;
+        let side = size as i32;
+        unsafe {
+            gl.CreateTextures(gl::TEXTURE_3D, 1, &mut texture);
+            gl.TextureStorage3D(texture, 1, gl::RGB8, side, side, side);
+            // Rows of RGB texels are only 4 byte aligned for some sizes.
+            gl.PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl.TextureSubImage3D(
+                texture,
+                0,
+                0,
+                0,
+                0,
+                side,
+                side,
+                side,
+                gl::RGB,
+                gl::UNSIGNED_BYTE,
+                data.as_ptr().cast(),
+            );
+            gl.PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+            gl.TextureParameteri(texture, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl.TextureParameteri(texture, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            for wrap in [gl::TEXTURE_WRAP_S, gl::TEXTURE_WRAP_T, gl::TEXTURE_WRAP_R] {
+                gl.TextureParameteri(texture, wrap, gl::CLAMP_TO_EDGE as i32);
+            }
+        }
+        Self {
+            gl: gl.clone(),
+            texture,
+            size,
+        }
+    }
+}
+
+fn check_lut_size(size: u32) -> Result<(), ImageError> {
+    if size < ColorLut::MIN_SIZE {
+        return Err(ImageError::Parameter(ParameterError::from_kind(
+            ParameterErrorKind::Generic(format!(
+                "Color LUTs need a size of at least {}, got {size}",
+                ColorLut::MIN_SIZE
+            )),
+        )));
+    }
+    Ok(())
+}
+
+impl Drop for ColorLut {
+    fn drop(&mut self) {
+        unsafe { self.gl.DeleteTextures(1, &self.texture) };
+    }
+}
+
+/// An ordered list of full screen effects run over the tone mapped scene before it reaches the
+/// default framebuffer. Passes render back and forth between two offscreen targets, the last
+/// one straight into the default framebuffer.
+pub struct PostProcess {
+    gl: Gl,
+    shader: PostProcessShader,
+    tone_map: ToneMap,
+    bloom: Bloom,
+    /// The scene is tone mapped into the first one.
+    targets: [Framebuffer; 2],
+    effects: Vec<PostEffect>,
+    lut: Option<ColorLut>,
+    /// Empty, the full screen triangle is made up from vertex indices alone.
+    vao: GLuint,
+}
+
+impl PostProcess {
+    pub fn new(
+        gl: &Gl,
+        shader: PostProcessShader,
+        tone_map: ToneMap,
+        bloom: Bloom,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, FramebufferError> {
+        let target = || {
+            Framebuffer::new(
+                gl,
+                FramebufferLayout::new().color(Attachment::texture(gl::SRGB8_ALPHA8)),
+                width,
+                height,
+            )
+        };
+        // Tone mapped colors are still linear, sRGB targets keep precision in the dark end
+        // and convert them back when sampled.
+        let targets = [target()?, target()?];
+        shader.screen_texture.set(TextureUnit(SCREEN_UNIT));
+        shader.color_lut.set(TextureUnit(LUT_UNIT));
+
+        let mut vao = 0;
+        unsafe { gl.CreateVertexArrays(1, &mut vao) };
+        Ok(Self {
+            gl: gl.clone(),
+            shader,
+            tone_map,
+            bloom,
+            targets,
+            effects: vec![],
+            lut: None,
+            vao,
+        })
+    }
+
+    pub fn effects(&self) -> &[PostEffect] {
+        &self.effects
+    }
+    /// The effects in the order they're applied, free to be changed between frames.
+    pub fn effects_mut(&mut self) -> &mut Vec<PostEffect> {
+        &mut self.effects
+    }
+
+    /// Removes `effect` if it's applied, otherwise appends it.
+    pub fn toggle(&mut self, effect: PostEffect) {
+        match self.effects.iter().position(|applied| *applied == effect) {
+            Some(index) => {
+                self.effects.remove(index);
+            }
+            None => self.effects.push(effect),
+        }
+    }
+
+    pub fn set_lut(&mut self, lut: Option<ColorLut>) {
+        self.lut = lut;
+    }
+
+    pub fn tone_map(&self) -> &ToneMap {
+        &self.tone_map
+    }
+    pub fn tone_map_mut(&mut self) -> &mut ToneMap {
+        &mut self.tone_map
+    }
+
+    pub fn bloom(&self) -> &Bloom {
+        &self.bloom
+    }
+    pub fn bloom_mut(&mut self) -> &mut Bloom {
+        &mut self.bloom
+    }
+
+    /// Resizes every target even if one fails, returning the first error.
+    pub fn resize(&mut self, width: i32, height: i32) -> Result<(), FramebufferError> {
+        let tone_map = self.tone_map.resize(width, height);
+        let bloom = self.bloom.resize(width, height);
+        let targets = self
+            .targets
+            .each_mut()
+            .map(|target| target.resize(width, height));
+        [tone_map, bloom].into_iter().chain(targets).collect()
+    }
+
+    /// Tone maps `scene` with bloom added, runs every effect over it and draws the result into
+    /// the default framebuffer, which is expected to be the same size.
+    pub fn apply(&self, scene: &Framebuffer) {
+        let gl = &self.gl;
+        let [first, second] = &self.targets;
+        unsafe {
+            gl.Disable(gl::DEPTH_TEST);
+            gl.BindVertexArray(self.vao);
+        }
+        self.tone_map.prepare(scene);
+        self.bloom.render(self.tone_map.bright_texture());
+
+        let effects: Vec<_> = self
+            .effects
+            .iter()
+            .filter(|effect| !matches!(effect, PostEffect::ColorGrade { .. }) || self.lut.is_some())
+            .collect();
+        let Some((last, passes)) = effects.split_last() else {
+            unsafe { gl.BindFramebuffer(gl::FRAMEBUFFER, 0) };
+            self.tone_map.draw(&self.bloom);
+            unsafe { gl.Enable(gl::DEPTH_TEST) };
+            return;
+        };
+
+        first.render(|| self.tone_map.draw(&self.bloom));
+        self.shader.shader.enable(gl);
+        if let Some(lut) = &self.lut {
+            unsafe { gl.BindTextureUnit(LUT_UNIT, lut.texture) };
+        }
+        let (mut source, mut target) = (first, second);
+        for effect in passes {
+            target.render(|| self.draw_pass(source, effect));
+            (source, target) = (target, source);
+        }
+        unsafe { gl.BindFramebuffer(gl::FRAMEBUFFER, 0) };
+        self.draw_pass(source, last);
+        unsafe { gl.Enable(gl::DEPTH_TEST) };
+    }
+
+    fn draw_pass(&self, source: &Framebuffer, effect: &PostEffect) {
+        let shader = &self.shader;
+        shader.effect.set(effect.id());
+        match *effect {
+            PostEffect::Kernel(kernel) => shader.kernel.set(kernel),
+            PostEffect::Vignette {
+                radius,
+                softness,
+                strength,
+            } => shader.vignette.set(vec3(radius, softness, strength)),
+            PostEffect::ColorGrade { strength } => shader.grade_strength.set(strength),
+            PostEffect::Grayscale | PostEffect::Invert => (),
+        }
+        unsafe {
+            self.gl
+                .BindTextureUnit(SCREEN_UNIT, source.color_texture(0).unwrap_or(0));
+            self.gl.DrawArrays(gl::TRIANGLES, 0, 3);
+        }
+    }
+}
+
+impl Drop for PostProcess {
+    fn drop(&mut self) {
+        unsafe { self.gl.DeleteVertexArrays(1, &self.vao) };
+    }
+}
@@ -0,0 +1,256 @@
+use glam::{vec3, Mat4, Vec3};
+use rand::random;
+
+use crate::{
+    gl::{self, types::GLuint, Gl},
+    shader::{GBufferShader, ShaderTrait, SsaoBlurShader, SsaoShader, TextureUnit, UniformGetSet},
+};
+
+use super::framebuffer::{Attachment, Framebuffer, FramebufferError, FramebufferLayout};
+
+/// Number of samples taken around each fragment.
+pub const SSAO_KERNEL_SIZE: usize = 32;
+/// Side of the tiled texture of random rotations, which the blur averages over.
+const NOISE_SIZE: i32 = 4;
+
+/// The sizes, defined in the SSAO shaders under these names.
+pub const SSAO_DEFINES: [(&str, i32); 2] = [
+    ("KERNEL_SIZE", SSAO_KERNEL_SIZE as i32),
+    ("NOISE_SIZE", NOISE_SIZE),
+];
+
+/// Texture unit the lit shader reads the blurred occlusion from.
+pub const SSAO_UNIT: GLuint = 6;
+
+// Texture units the SSAO passes sample from.
+const NORMAL_UNIT: GLuint = 0;
+const DEPTH_UNIT: GLuint = 1;
+const NOISE_UNIT: GLuint = 2;
+const SOURCE_UNIT: GLuint = 0;
+
+const RADIUS_DEFAULT: f32 = 0.5;
+const BIAS_DEFAULT: f32 = 0.025;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SsaoSettings {
+    pub enabled: bool,
+    /// View space distance around a fragment that's searched for occluders.
+    pub radius: f32,
+    /// Depth difference below which a sample isn't counted as occluded, against acne on flat
+    /// surfaces.
+    pub bias: f32,
+}
+
+impl Default for SsaoSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            radius: RADIUS_DEFAULT,
+            bias: BIAS_DEFAULT,
+        }
+    }
+}
+
+/// Screen space ambient occlusion. The lit geometry's view space normals and depth are drawn
+/// into a G-buffer, from which the share of samples in a hemisphere around each fragment that
+/// end up behind other geometry is counted and blurred. The result is bound to `SSAO_UNIT`,
+/// where the lit shader darkens the ambient light with it.
+pub struct Ssao {
+    gl: Gl,
+    gbuffer_shader: GBufferShader,
+    shader: SsaoShader,
+    blur_shader: SsaoBlurShader,
+    settings: SsaoSettings,
+    gbuffer: Framebuffer,
+    occlusion_target: Framebuffer,
+    blur_target: Framebuffer,
+    noise: GLuint,
+    /// Empty, the full screen triangle is made up from vertex indices alone.
+    vao: GLuint,
+}
+
+impl Ssao {
+    pub fn new(
+        gl: &Gl,
+        gbuffer_shader: GBufferShader,
+        shader: SsaoShader,
+        blur_shader: SsaoBlurShader,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, FramebufferError> {
+        let gbuffer = Framebuffer::new(
+            gl,
+            FramebufferLayout::new()
+                .color(Attachment::texture(gl::RGBA16F))
+                .depth(Attachment::texture(gl::DEPTH_COMPONENT32F)),
+            width,
+            height,
+        )?;
+        let target = || {
+            Framebuffer::new(
+                gl,
+                FramebufferLayout::new().color(Attachment::texture(gl::R8)),
+                width,
+                height,
+            )
+        };
+        let occlusion_target = target()?;
+        let blur_target = target()?;
+
+        shader.normal.set(TextureUnit(NORMAL_UNIT));
+        shader.depth.set(TextureUnit(DEPTH_UNIT));
+        shader.noise.set(TextureUnit(NOISE_UNIT));
+        shader.samples.set(kernel());
+        blur_shader.source.set(TextureUnit(SOURCE_UNIT));
+
+        let mut vao = 0;
+        unsafe { gl.CreateVertexArrays(1, &mut vao) };
+        let ssao = Self {
+            gl: gl.clone(),
+            gbuffer_shader,
+            shader,
+            blur_shader,
+            settings: SsaoSettings::default(),
+            gbuffer,
+            occlusion_target,
+            blur_target,
+            noise: noise_texture(gl),
+            vao,
+        };
+        ssao.set_uniforms();
+        Ok(ssao)
+    }
+
+    pub fn settings(&self) -> SsaoSettings {
+        self.settings
+    }
+    pub fn set_settings(&mut self, settings: SsaoSettings) {
+        self.settings = settings;
+        self.set_uniforms();
+    }
+
+    /// Resizes every target even if one fails, returning the first error.
+    pub fn resize(&mut self, width: i32, height: i32) -> Result<(), FramebufferError> {
+        [
+            self.gbuffer.resize(width, height),
+            self.occlusion_target.resize(width, height),
+            self.blur_target.resize(width, height),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    /// The blurred occlusion, 1 where nothing is occluded, valid after `render`.
+    pub fn texture(&self) -> GLuint {
+        self.blur_target.color_texture(0).unwrap_or(0)
+    }
+
+    /// Computes the occlusion of the geometry `draw` draws with the shader it's given and binds
+    /// it to `SSAO_UNIT`. `projection` has to match the one in the `CameraBlock`. When disabled
+    /// nothing is occluded.
+    pub fn render(&self, projection: Mat4, draw: impl FnOnce(&GBufferShader)) {
+        let gl = &self.gl;
+        if !self.settings.enabled {
+            unsafe {
+                gl.ClearNamedFramebufferfv(self.blur_target.id(), gl::COLOR, 0, [1.0; 4].as_ptr());
+                gl.BindTextureUnit(SSAO_UNIT, self.texture());
+            }
+            return;
+        }
+
+        self.gbuffer.render(|| unsafe {
+            gl.ClearColor(0.0, 0.0, 0.0, 0.0);
+            gl.Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+            draw(&self.gbuffer_shader);
+        });
+
+        unsafe {
+            gl.Disable(gl::DEPTH_TEST);
+            gl.BindVertexArray(self.vao);
+        }
+        self.shader.inverse_projection.set(projection.inverse());
+        self.shader.shader.enable(gl);
+        self.occlusion_target.render(|| unsafe {
+            gl.BindTextureUnit(NORMAL_UNIT, self.gbuffer.color_texture(0).unwrap_or(0));
+            gl.BindTextureUnit(DEPTH_UNIT, self.gbuffer.depth_texture().unwrap_or(0));
+            gl.BindTextureUnit(NOISE_UNIT, self.noise);
+            gl.DrawArrays(gl::TRIANGLES, 0, 3);
+        });
+        self.blur_shader.shader.enable(gl);
+        self.blur_target.render(|| unsafe {
+            gl.BindTextureUnit(
+                SOURCE_UNIT,
+                self.occlusion_target.color_texture(0).unwrap_or(0),
+            );
+            gl.DrawArrays(gl::TRIANGLES, 0, 3);
+        });
+        unsafe {
+            gl.Enable(gl::DEPTH_TEST);
+            gl.BindTextureUnit(SSAO_UNIT, self.texture());
+        }
+    }
+
+    fn set_uniforms(&self) {
+        self.shader.radius.set(self.settings.radius);
+        self.shader.bias.set(self.settings.bias);
+    }
+}
+
+impl Drop for Ssao {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.DeleteTextures(1, &self.noise);
+            self.gl.DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
+
+/// Random offsets in the hemisphere around +z, within a radius of 1. Later samples are pushed
+/// further out so most of them land close to the fragment, where occluders matter most.
+fn kernel() -> [Vec3; SSAO_KERNEL_SIZE] {
+    std::array::from_fn(|index| {
+        let dir = vec3(
+            random::<f32>() * 2.0 - 1.0,
+            random::<f32>() * 2.0 - 1.0,
+            random::<f32>(),
+        )
+        .normalize_or(Vec3::Z);
+        let scale = index as f32 / SSAO_KERNEL_SIZE as f32;
+        dir * random::<f32>() * (0.1 + 0.9 * scale * scale)
+    })
+}
+
+/// Random rotations around z, tiled over the screen so neighbouring fragments sample the
+/// kernel differently. The blur then averages the banding this would otherwise cause away.
+fn noise_texture(gl: &Gl) -> GLuint {
+    let data: Vec<f32> = (0..NOISE_SIZE * NOISE_SIZE)
+        .flat_map(|_| {
+            [
+                random::<f32>() * 2.0 - 1.0,
+                random::<f32>() * 2.0 - 1.0,
+                0.0,
+            ]
+        })
+        .collect();
+    let mut texture = 0;
+    unsafe {
+        gl.CreateTextures(gl::TEXTURE_2D, 1, &mut texture);
+        gl.TextureStorage2D(texture, 1, gl::RGB16F, NOISE_SIZE, NOISE_SIZE);
+        gl.TextureSubImage2D(
+            texture,
+            0,
+            0,
+            0,
+            NOISE_SIZE,
+            NOISE_SIZE,
+            gl::RGB,
+            gl::FLOAT,
+            data.as_ptr().cast(),
+        );
+        gl.TextureParameteri(texture, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+        gl.TextureParameteri(texture, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+        gl.TextureParameteri(texture, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+        gl.TextureParameteri(texture, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+    }
+    texture
+}
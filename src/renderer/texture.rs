@@ -1,15 +1,46 @@
-use std::{collections::HashMap, os::raw::c_void};
+use std::{collections::HashMap, os::raw::c_void, path::Path};
 
-use image::ImageReader;
+use glam::Vec4;
+use image::{DynamicImage, ImageError, ImageReader, Rgba, RgbaImage};
 
-use crate::{
-    gl::{self, types::GLuint, Gl},
-    shader::{Shader, ShaderTrait},
+use crate::gl::{
+    self,
+    types::{GLenum, GLuint},
+    Gl,
 };
 
+/// How the values of a texture's texels are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    /// Colors meant to be looked at, like diffuse maps. They're stored gamma encoded and
+    /// converted to linear values when sampled.
+    Srgb,
+    /// Data that's used as is, like specular, normal and ambient occlusion maps.
+    Linear,
+}
+
+impl ColorSpace {
+    pub fn internal_format(self) -> GLenum {
+        match self {
+            ColorSpace::Srgb => gl::SRGB8_ALPHA8,
+            ColorSpace::Linear => gl::RGBA8,
+        }
+    }
+}
+
+/// An uploaded image and how its texels are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Texture {
+    pub id: GLuint,
+    pub color_space: ColorSpace,
+}
+
+/// Keeps track of uploaded images so the same one isn't uploaded twice. An image loaded in
+/// both color spaces gets a texture for each.
 #[derive(Default)]
 pub struct TextureManager {
-    texture_name_map: HashMap<String, GLuint>,
+    texture_name_map: HashMap<String, Texture>,
+    texture_path_map: HashMap<(String, ColorSpace), Texture>,
 }
 
 impl TextureManager {
@@ -17,54 +48,99 @@ impl TextureManager {
         Self::default()
     }
 
-    pub fn create_texture(
+    /// Loads the image at `path` into a texture bound by `bind_texture` under `name`. The
+    /// sampler reading it is set up separately.
+    pub fn create_texture(&mut self, gl: &Gl, name: &str, path: &str, color_space: ColorSpace) {
+        let img = ImageReader::open(path).unwrap().decode().unwrap();
+        let texture = upload_image(gl, &img, color_space);
+
+        self.texture_name_map.insert(name.to_string(), texture);
+    }
+
+    /// Loads the image at `path` into a texture, or returns the texture it was already loaded
+    /// into.
+    pub fn load_texture(
         &mut self,
         gl: &Gl,
-        name: &str,
-        path: &str,
-        program: &Shader,
-        index: i32,
-    ) {
-        let img = ImageReader::open(path).unwrap().decode().unwrap().flipv();
-
-        let img_height = img.height();
-        let img_width = img.width();
-        let data = img.to_rgba8();
-
-        let mut texture: GLuint = 0;
-        unsafe {
-            gl.GenTextures(1, &mut texture);
-            gl.BindTexture(gl::TEXTURE_2D, texture);
-            gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
-            gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
-            gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
-            gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
-            gl.TexImage2D(
-                gl::TEXTURE_2D,
-                0,
-                gl::RGB as i32,
-                img_width as i32,
-                img_height as i32,
-                0,
-                gl::RGBA,
-                gl::UNSIGNED_BYTE,
-                data.as_ptr() as *const c_void,
-            );
-            gl.GenerateMipmap(gl::TEXTURE_2D);
-        };
-        program.set_int(gl, name, index).unwrap();
+        path: &Path,
+        color_space: ColorSpace,
+    ) -> Result<Texture, ImageError> {
+        let key = (path.to_string_lossy().into_owned(), color_space);
+        if let Some(texture) = self.texture_path_map.get(&key) {
+            return Ok(*texture);
+        }
 
-        self.texture_name_map.insert(name.to_string(), texture);
+        let img = ImageReader::open(path)?.decode()?;
+        let texture = upload_image(gl, &img, color_space);
+        self.texture_path_map.insert(key, texture);
+        Ok(texture)
+    }
+
+    /// Uploads an already decoded image, `key` identifies it for later calls so the same image
+    /// isn't uploaded twice.
+    pub fn load_image(
+        &mut self,
+        gl: &Gl,
+        key: &str,
+        img: &DynamicImage,
+        color_space: ColorSpace,
+    ) -> Texture {
+        *self
+            .texture_path_map
+            .entry((key.to_string(), color_space))
+            .or_insert_with(|| upload_image(gl, img, color_space))
+    }
+
+    /// A single texel texture of `color`, which is taken to be linear already.
+    pub fn load_color(&mut self, gl: &Gl, color: Vec4) -> Texture {
+        let texel = (color.clamp(Vec4::ZERO, Vec4::ONE) * 255.0).round();
+        let texel = [texel.x as u8, texel.y as u8, texel.z as u8, texel.w as u8];
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba(texel)));
+        self.load_image(gl, &format!("color:{texel:?}"), &img, ColorSpace::Linear)
     }
 
     pub fn bind_texture(&self, gl: &Gl, name: &str, unit: GLuint) {
         let texture = self.get_texture(name).unwrap();
         unsafe {
-            gl.BindTextureUnit(unit, *texture);
+            gl.BindTextureUnit(unit, texture.id);
         }
     }
 
-    fn get_texture(&self, name: &str) -> Option<&GLuint> {
+    fn get_texture(&self, name: &str) -> Option<&Texture> {
         self.texture_name_map.get(name)
     }
 }
+
+fn upload_image(gl: &Gl, img: &DynamicImage, color_space: ColorSpace) -> Texture {
+    let img = img.flipv();
+
+    let img_height = img.height();
+    let img_width = img.width();
+    let data = img.to_rgba8();
+
+    let mut texture: GLuint = 0;
+    unsafe {
+        gl.GenTextures(1, &mut texture);
+        gl.BindTexture(gl::TEXTURE_2D, texture);
+        gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+        gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+        gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+        gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+        gl.TexImage2D(
+            gl::TEXTURE_2D,
+            0,
+            color_space.internal_format() as i32,
+            img_width as i32,
+            img_height as i32,
+            0,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            data.as_ptr() as *const c_void,
+        );
+        gl.GenerateMipmap(gl::TEXTURE_2D);
+    };
+    Texture {
+        id: texture,
+        color_space,
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{
+    gl::{self, types::GLuint, Gl},
+    shader::{LuminanceShader, ShaderTrait, TextureUnit, ToneMapShader, UniformGetSet},
+};
+
+use super::{
+    bloom::Bloom,
+    framebuffer::{Attachment, Framebuffer, FramebufferError, FramebufferLayout},
+};
+
+// Values of the tone map shader's `toneMapOperator` uniform.
+const TONE_MAP_REINHARD: i32 = 0;
+const TONE_MAP_EXPOSURE: i32 = 1;
+const TONE_MAP_ACES: i32 = 2;
+
+/// The operator values, defined in the tone map shader under these names.
+pub const TONE_MAP_DEFINES: [(&str, i32); 3] = [
+    ("TONE_MAP_REINHARD", TONE_MAP_REINHARD),
+    ("TONE_MAP_EXPOSURE", TONE_MAP_EXPOSURE),
+    ("TONE_MAP_ACES", TONE_MAP_ACES),
+];
+
+// Texture units the tone map shader samples from.
+const HDR_UNIT: GLuint = 0;
+const LUMINANCE_UNIT: GLuint = 1;
+const BLOOM_UNIT: GLuint = 2;
+
+const EXPOSURE_DEFAULT: f32 = 1.0;
+
+/// How colors past 1 are brought back into the displayable range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToneMapOperator {
+    /// `c / (c + 1)`, which desaturates bright colors.
+    Reinhard,
+    /// `1 - e^-c`, like film exposed for longer.
+    Exposure,
+    /// An approximation of the ACES filmic curve, with more contrast than the others.
+    Aces,
+}
+
+impl ToneMapOperator {
+    /// The operator after this one, wrapping around, for cycling through them.
+    pub fn next(self) -> Self {
+        match self {
+            ToneMapOperator::Reinhard => ToneMapOperator::Exposure,
+            ToneMapOperator::Exposure => ToneMapOperator::Aces,
+            ToneMapOperator::Aces => ToneMapOperator::Reinhard,
+        }
+    }
+
+    fn id(self) -> i32 {
+        match self {
+            ToneMapOperator::Reinhard => TONE_MAP_REINHARD,
+            ToneMapOperator::Exposure => TONE_MAP_EXPOSURE,
+            ToneMapOperator::Aces => TONE_MAP_ACES,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneMapSettings {
+    pub operator: ToneMapOperator,
+    /// Colors are multiplied by this before the operator is applied.
+    pub exposure: f32,
+    /// Scales the exposure so the scene's average luminance lands on middle gray, making
+    /// `exposure` a compensation on top of that.
+    pub auto_exposure: bool,
+}
+
+impl Default for ToneMapSettings {
+    fn default() -> Self {
+        Self {
+            operator: ToneMapOperator::Aces,
+            exposure: EXPOSURE_DEFAULT,
+            auto_exposure: false,
+        }
+    }
+}
+
+/// Resolves a floating point scene and tone maps it into whatever framebuffer is bound.
+pub struct ToneMap {
+    gl: Gl,
+    shader: ToneMapShader,
+    luminance_shader: LuminanceShader,
+    settings: ToneMapSettings,
+    /// The scene resolved, and its bright fragments.
+    hdr_target: Framebuffer,
+    /// Log luminance of the scene, whose mip chain averages it for auto exposure.
+    luminance_target: Framebuffer,
+}
+
+impl ToneMap {
+    pub fn new(
+        gl: &Gl,
+        shader: ToneMapShader,
+        luminance_shader: LuminanceShader,
+        width: i32,
+        height: i32,
+    ) -> Result<Self, FramebufferError> {
+        let hdr_target = Framebuffer::new(
+            gl,
+            FramebufferLayout::new()
+                .color(Attachment::texture(gl::RGBA16F))
+                .color(Attachment::texture(gl::RGBA16F)),
+            width,
+            height,
+        )?;
+        let luminance_target = Framebuffer::new(
+            gl,
+            FramebufferLayout::new().color(Attachment::mipmapped_texture(gl::R16F)),
+            width,
+            height,
+        )?;
+        shader.hdr_texture.set(TextureUnit(HDR_UNIT));
+        shader.luminance.set(TextureUnit(LUMINANCE_UNIT));
+        shader.bloom.set(TextureUnit(BLOOM_UNIT));
+        luminance_shader.hdr_texture.set(TextureUnit(HDR_UNIT));
+
+        let tone_map = Self {
+            gl: gl.clone(),
+            shader,
+            luminance_shader,
+            settings: ToneMapSettings::default(),
+            hdr_target,
+            luminance_target,
+        };
+        tone_map.set_uniforms();
+        Ok(tone_map)
+    }
+
+    pub fn settings(&self) -> ToneMapSettings {
+        self.settings
+    }
+    pub fn set_settings(&mut self, settings: ToneMapSettings) {
+        self.settings = settings;
+        self.set_uniforms();
+    }
+
+    /// Resizes both targets even if one fails, returning the first error.
+    pub fn resize(&mut self, width: i32, height: i32) -> Result<(), FramebufferError> {
+        [
+            self.hdr_target.resize(width, height),
+            self.luminance_target.resize(width, height),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    /// The bright fragments of the scene resolved by `prepare`, for `Bloom::render`.
+    pub fn bright_texture(&self) -> GLuint {
+        self.hdr_target.color_texture(1).unwrap_or(0)
+    }
+
+    /// Resolves `scene` and measures its luminance if auto exposure needs it. The full screen
+    /// triangle's vertex array needs to be bound.
+    pub fn prepare(&self, scene: &Framebuffer) {
+        scene.resolve(&self.hdr_target);
+        if !self.settings.auto_exposure {
+            return;
+        }
+        self.luminance_target.render(|| {
+            self.luminance_shader.shader.enable(&self.gl);
+            unsafe {
+                self.gl
+                    .BindTextureUnit(HDR_UNIT, self.hdr_target.color_texture(0).unwrap_or(0));
+                self.gl.DrawArrays(gl::TRIANGLES, 0, 3);
+            }
+        });
+        self.luminance_target.generate_mipmaps();
+    }
+
+    /// Draws the scene resolved by `prepare` with `bloom` added on top, tone mapped, into the
+    /// bound framebuffer.
+    pub fn draw(&self, bloom: &Bloom) {
+        let gl = &self.gl;
+        let bloom_settings = bloom.settings();
+        let bloom_intensity = if bloom_settings.enabled {
+            bloom_settings.intensity
+        } else {
+            0.0
+        };
+        self.shader.bloom_intensity.set(bloom_intensity);
+        self.shader.shader.enable(gl);
+        unsafe {
+            gl.BindTextureUnit(BLOOM_UNIT, bloom.texture());
+            gl.BindTextureUnit(HDR_UNIT, self.hdr_target.color_texture(0).unwrap_or(0));
+            gl.BindTextureUnit(
+                LUMINANCE_UNIT,
+                self.luminance_target.color_texture(0).unwrap_or(0),
+            );
+            gl.DrawArrays(gl::TRIANGLES, 0, 3);
+        }
+    }
+
+    fn set_uniforms(&self) {
+        let shader = &self.shader;
+        shader.operator.set(self.settings.operator.id());
+        shader.exposure.set(self.settings.exposure);
+        shader.auto_exposure.set(self.settings.auto_exposure);
+    }
+}
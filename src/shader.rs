@@ -1,353 +1,928 @@
-use std::{fs, marker::PhantomData, mem::zeroed};
+mod block;
+mod error;
+mod preprocessor;
+mod reflection;
+mod uniform;
+mod watcher;
+
+use std::{
+    cell::{Cell, Ref, RefCell},
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::SystemTime,
+};
 
-use glam::{Mat4, Vec3};
+use glam::{Mat3, Mat4, Vec2, Vec3};
 
 use crate::{
+    camera::CameraBlock,
     gl::{
-        self, create_shader,
-        types::{GLfloat, GLsizei, GLuint},
+        self, create_shader, get_program_info_log,
+        types::{GLint, GLuint},
         Gl,
     },
     helper::add_null_term,
+    light::{DirectionLightsBlock, LightsBlock, PointLightsBlock, SpotLightsBlock},
+    renderer::{
+        bloom::BloomBlock,
+        post_process::EFFECT_DEFINES,
+        ssao::{SSAO_DEFINES, SSAO_KERNEL_SIZE, SSAO_UNIT},
+        tone_map::TONE_MAP_DEFINES,
+    },
+    shadow::{ShadowBlock, DIRECTIONAL_SHADOW_UNIT, POINT_SHADOW_UNIT, SPOT_SHADOW_UNIT},
+};
+
+pub use self::block::{
+    check_layout, check_storage_layout, BufferBlock, Std140, StorageArray, StorageBuffer,
+    UniformBuffer, BLOOM_BINDING, CAMERA_BINDING, DIRECTION_LIGHTS_BINDING, LIGHTS_BINDING,
+    MATERIAL_BINDING, POINT_LIGHTS_BINDING, SHADOW_BINDING, SPOT_LIGHTS_BINDING,
 };
+pub use self::error::{
+    parse_info_log, BlockError, LogMessage, ShaderError, ShaderStage, UniformError,
+};
+pub use self::preprocessor::{Preprocessor, Source};
+pub use self::reflection::{
+    glsl_type_name, ActiveAttribute, ActiveUniform, Reflection, StorageBlock, UniformBlock,
+};
+pub use self::uniform::{ShaderUniforms, TextureUnit, Uniform, UniformGetSet, UniformValue};
+
+use self::uniform::{LastValue, UniformSlot};
+pub use self::watcher::ShaderWatcher;
+pub use learn_ogl_rs_derive::{BufferBlock, ShaderUniforms, Std140};
 
 pub trait ShaderTrait {
     fn get_id(&self) -> GLuint;
 
     fn enable(&self, gl: &Gl);
 
-    fn set_bool(&self, gl: &Gl, name: &str, val: bool) -> Result<(), String>;
-    fn set_int(&self, gl: &Gl, name: &str, val: i32) -> Result<(), String>;
-    fn set_float(&self, gl: &Gl, name: &str, val: f32) -> Result<(), String>;
-    fn set_vec2(&self, gl: &Gl, name: &str, val: (f32, f32)) -> Result<(), String>;
-    fn set_vec3(&self, gl: &Gl, name: &str, val: (f32, f32, f32)) -> Result<(), String>;
-    fn get_vec3(&self, gl: &Gl, name: &str) -> Result<Vec3, String>;
-    fn set_mat4(&self, gl: &Gl, name: &str, val: Mat4) -> Result<(), String>;
+    fn set_bool(&self, gl: &Gl, name: &str, val: bool) -> Result<(), UniformError>;
+    fn set_int(&self, gl: &Gl, name: &str, val: i32) -> Result<(), UniformError>;
+    fn set_float(&self, gl: &Gl, name: &str, val: f32) -> Result<(), UniformError>;
+    fn set_vec2(&self, gl: &Gl, name: &str, val: (f32, f32)) -> Result<(), UniformError>;
+    fn set_vec3(&self, gl: &Gl, name: &str, val: (f32, f32, f32)) -> Result<(), UniformError>;
+    fn get_vec3(&self, gl: &Gl, name: &str) -> Result<Vec3, UniformError>;
+    fn set_mat4(&self, gl: &Gl, name: &str, val: Mat4) -> Result<(), UniformError>;
 }
 
+/// Handle to a linked program. Clones share the program, so a reload through any of them is
+/// seen by all of them and by every `Uniform` created from them.
 #[derive(Clone)]
 pub struct Shader {
-    program_id: GLuint,
+    program: Rc<Program>,
+}
+
+type BlockCheck = fn(&Reflection) -> Result<(), BlockError>;
+
+struct Program {
+    id: Cell<GLuint>,
+    /// Bumped every time the program is relinked so uniform locations can be re-resolved.
+    generation: Cell<u32>,
+    vertex_path: PathBuf,
+    geometry_path: Option<PathBuf>,
+    fragment_path: PathBuf,
+    preprocessor: Preprocessor,
+    /// Every file the program was built from, including the ones included by the preprocessor.
+    sources: RefCell<Vec<PathBuf>>,
+    modified: RefCell<Vec<Option<SystemTime>>>,
+    reflection: RefCell<Reflection>,
+    /// The last value set for each uniform, replayed after a reload since a freshly linked
+    /// program starts with every uniform zeroed. Only touched when a `Uniform` is created, a
+    /// uniform is set by name, or the program is reloaded.
+    values: RefCell<HashMap<String, Rc<dyn UniformSlot>>>,
+    /// Layouts of the uniform blocks the program is expected to have, see `check_block`.
+    blocks: RefCell<Vec<BlockCheck>>,
+}
+
+impl Program {
+    fn stages(&self) -> Vec<(ShaderStage, &Path)> {
+        let mut stages = vec![(ShaderStage::Vertex, self.vertex_path.as_path())];
+        if let Some(geometry_path) = &self.geometry_path {
+            stages.push((ShaderStage::Geometry, geometry_path.as_path()));
+        }
+        stages.push((ShaderStage::Fragment, self.fragment_path.as_path()));
+        stages
+    }
 }
 
 impl ShaderTrait for Shader {
     fn get_id(&self) -> GLuint {
-        self.program_id
+        self.program.id.get()
     }
 
     fn enable(&self, gl: &Gl) {
         unsafe {
-            gl.UseProgram(self.program_id);
+            gl.UseProgram(self.get_id());
         }
     }
 
-    fn set_bool(&self, gl: &Gl, name: &str, val: bool) -> Result<(), String> {
-        match self.get_uniform_id(gl, name) {
-            Ok(id) => {
-                self.enable(gl);
-                unsafe {
-                    gl.Uniform1i(id, val.into());
-                }
-                Ok(())
-            }
-            Err(err) => Err(err),
-        }
+    fn set_bool(&self, gl: &Gl, name: &str, val: bool) -> Result<(), UniformError> {
+        self.set(gl, name, val)
     }
 
-    fn set_int(&self, gl: &Gl, name: &str, val: i32) -> Result<(), String> {
-        match self.get_uniform_id(gl, name) {
-            Ok(id) => {
-                self.enable(gl);
+    fn set_int(&self, gl: &Gl, name: &str, val: i32) -> Result<(), UniformError> {
+        self.set(gl, name, val)
+    }
 
-                unsafe {
-                    gl.Uniform1i(id, val);
-                }
-                Ok(())
+    fn set_float(&self, gl: &Gl, name: &str, val: f32) -> Result<(), UniformError> {
+        self.set(gl, name, val)
+    }
+
+    fn set_vec2(&self, gl: &Gl, name: &str, val: (f32, f32)) -> Result<(), UniformError> {
+        self.set(gl, name, Vec2::from(val))
+    }
+    fn set_vec3(&self, gl: &Gl, name: &str, val: (f32, f32, f32)) -> Result<(), UniformError> {
+        self.set(gl, name, Vec3::from(val))
+    }
+    fn get_vec3(&self, gl: &Gl, name: &str) -> Result<Vec3, UniformError> {
+        self.get(gl, name)
+    }
+    fn set_mat4(&self, gl: &Gl, name: &str, val: Mat4) -> Result<(), UniformError> {
+        self.set(gl, name, val)
+    }
+}
+
+impl Shader {
+    pub fn new(gl: &Gl, vertex_path: &str, fragment_path: &str) -> Result<Self, ShaderError> {
+        Self::with_preprocessor(gl, vertex_path, fragment_path, Preprocessor::new())
+    }
+
+    /// Builds a program from sources that are first run through `preprocessor`, both when
+    /// created and on every reload.
+    pub fn with_preprocessor(
+        gl: &Gl,
+        vertex_path: &str,
+        fragment_path: &str,
+        preprocessor: Preprocessor,
+    ) -> Result<Self, ShaderError> {
+        Self::build(gl, vertex_path, None, fragment_path, preprocessor)
+    }
+
+    /// Like `new`, with a geometry stage between the vertex and fragment stages.
+    pub fn with_geometry(
+        gl: &Gl,
+        vertex_path: &str,
+        geometry_path: &str,
+        fragment_path: &str,
+    ) -> Result<Self, ShaderError> {
+        Self::build(
+            gl,
+            vertex_path,
+            Some(geometry_path),
+            fragment_path,
+            Preprocessor::new(),
+        )
+    }
+
+    fn build(
+        gl: &Gl,
+        vertex_path: &str,
+        geometry_path: Option<&str>,
+        fragment_path: &str,
+        preprocessor: Preprocessor,
+    ) -> Result<Self, ShaderError> {
+        let program = Program {
+            id: Cell::new(0),
+            generation: Cell::new(0),
+            vertex_path: PathBuf::from(vertex_path),
+            geometry_path: geometry_path.map(PathBuf::from),
+            fragment_path: PathBuf::from(fragment_path),
+            preprocessor,
+            modified: RefCell::new(vec![]),
+            sources: RefCell::new(vec![]),
+            reflection: RefCell::new(Reflection::default()),
+            values: RefCell::new(HashMap::new()),
+            blocks: RefCell::new(vec![]),
+        };
+        let (program_id, sources) = link_program(gl, &program.stages(), &program.preprocessor)?;
+        program.id.set(program_id);
+        program.modified.replace(modified_times(&sources));
+        program.sources.replace(sources);
+        program.reflection.replace(Reflection::new(gl, program_id));
+        Ok(Self {
+            program: Rc::new(program),
+        })
+    }
+
+    /// Recompiles the program from its source files. If that fails, a block checked with
+    /// `check_block` no longer matches, or a vertex attribute would end up at another location,
+    /// the previous program is kept. Otherwise every uniform set so far is set again on the new
+    /// program.
+    ///
+    /// Vertex arrays aren't set up again, so attributes need to keep their locations, which
+    /// `layout (location = ...)` guarantees.
+    pub fn reload(&self, gl: &Gl) -> Result<(), ShaderError> {
+        let program = &self.program;
+        program
+            .modified
+            .replace(modified_times(&program.sources.borrow()));
+
+        let (program_id, sources) = link_program(gl, &program.stages(), &program.preprocessor)?;
+        let reflection = Reflection::new(gl, program_id);
+        let checked = program
+            .blocks
+            .borrow()
+            .iter()
+            .try_for_each(|check| check(&reflection))
+            .map_err(ShaderError::from)
+            .and_then(|()| check_attributes(&program.reflection.borrow(), &reflection));
+        if let Err(err) = checked {
+            unsafe { gl.DeleteProgram(program_id) };
+            return Err(err);
+        }
+        program.modified.replace(modified_times(&sources));
+        program.sources.replace(sources);
+        program.reflection.replace(reflection);
+        unsafe { gl.DeleteProgram(program.id.replace(program_id)) };
+        program.generation.set(program.generation.get() + 1);
+
+        let reflection = program.reflection.borrow();
+        for (name, slot) in program.values.borrow().iter() {
+            if let Some((location, _)) = reflection.uniform(name) {
+                slot.replay(gl, program_id, location);
             }
-            Err(err) => Err(err),
         }
+        Ok(())
     }
 
-    fn set_float(&self, gl: &Gl, name: &str, val: f32) -> Result<(), String> {
-        match self.get_uniform_id(gl, name) {
-            Ok(id) => {
-                self.enable(gl);
-                unsafe {
-                    gl.Uniform1f(id, val);
-                }
-                Ok(())
-            }
-            Err(err) => Err(err),
+    /// Whether any source file was modified since the program was last (re)loaded.
+    pub fn sources_changed(&self) -> bool {
+        let program = &self.program;
+        *program.modified.borrow() != modified_times(&program.sources.borrow())
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.program.generation.get()
+    }
+
+    pub fn same_program(&self, other: &Shader) -> bool {
+        Rc::ptr_eq(&self.program, &other.program)
+    }
+
+    /// The active uniforms, uniform blocks and attributes of the current program.
+    pub fn reflection(&self) -> Ref<'_, Reflection> {
+        self.program.reflection.borrow()
+    }
+
+    /// Sets a uniform by name, checking its type against the program's reflection. Prefer a
+    /// `Uniform` handle for uniforms that are set repeatedly.
+    pub fn set<T: UniformValue>(&self, gl: &Gl, name: &str, val: T) -> Result<(), UniformError> {
+        let location = self.uniform_locations::<T>(name)?[0];
+        unsafe { T::set(gl, self.get_id(), location, &[val]) };
+        self.last_value::<T>(name).set(val);
+        Ok(())
+    }
+
+    pub fn get<T: UniformValue>(&self, gl: &Gl, name: &str) -> Result<T, UniformError> {
+        let locations = self.uniform_locations::<T>(name)?;
+        Ok(unsafe { T::get(gl, self.get_id(), &locations) })
+    }
+
+    /// Checks that the program's `T` block matches the layout of `T`, now and after every
+    /// reload.
+    pub fn check_block<T: BufferBlock>(&self) -> Result<(), BlockError> {
+        self.add_block_check(check_layout::<T>)
+    }
+
+    /// Like `check_block`, for storage blocks.
+    pub fn check_storage_block<T: StorageArray>(&self) -> Result<(), BlockError> {
+        self.add_block_check(check_storage_layout::<T>)
+    }
+
+    fn add_block_check(&self, check: BlockCheck) -> Result<(), BlockError> {
+        check(&self.reflection())?;
+        self.program.blocks.borrow_mut().push(check);
+        Ok(())
+    }
+
+    /// Checks that every active member of the struct uniform `prefix` is one of `names`, so a
+    /// member added to the GLSL struct can't silently be left unset.
+    pub fn check_uniform_struct(&self, prefix: &str, names: &[&str]) -> Result<(), UniformError> {
+        let reflection = self.reflection();
+        let unbound = reflection.uniforms().iter().find(|uniform| {
+            let Some(member) = uniform
+                .name
+                .strip_prefix(prefix)
+                .and_then(|name| name.strip_prefix('.'))
+            else {
+                return false;
+            };
+            let member = member.split(['.', '[']).next().unwrap_or(member);
+            !names.contains(&member)
+        });
+        match unbound {
+            Some(uniform) => Err(UniformError::Unbound(uniform.name.clone())),
+            None => Ok(()),
         }
     }
 
-    fn set_vec2(&self, gl: &Gl, name: &str, val: (f32, f32)) -> Result<(), String> {
-        match self.get_uniform_id(gl, name) {
-            Ok(id) => {
-                self.enable(gl);
-                unsafe {
-                    gl.Uniform2f(id, val.0, val.1);
-                }
-                Ok(())
-            }
-            Err(err) => Err(err),
+    /// Looks up the location of `name` and, for arrays, of each element after it, checking
+    /// that `T` can be set to it. Arrays need to be exactly as long as `T`.
+    fn uniform_locations<T: UniformValue>(&self, name: &str) -> Result<Vec<GLint>, UniformError> {
+        let reflection = self.reflection();
+        let (location, uniform) = reflection
+            .uniform(name)
+            .ok_or_else(|| UniformError::NotFound(name.to_string()))?;
+
+        if !T::accepts(uniform.gl_type) {
+            return Err(UniformError::TypeMismatch {
+                name: name.to_string(),
+                expected: T::GL_TYPE,
+                found: uniform.gl_type,
+            });
+        }
+        if T::LEN == 1 {
+            return Ok(vec![location]);
+        }
+
+        if !uniform.name.ends_with("[0]") || uniform.size != T::LEN as GLint {
+            return Err(UniformError::LengthMismatch {
+                name: name.to_string(),
+                expected: T::LEN,
+                found: uniform.size,
+            });
         }
+        let base = name.strip_suffix("[0]").unwrap_or(name);
+        let mut locations = vec![location];
+        locations.extend((1..T::LEN).map(|element| {
+            reflection
+                .uniform(&format!("{base}[{element}]"))
+                .map_or(-1, |(location, _)| location)
+        }));
+        Ok(locations)
     }
-    fn set_vec3(&self, gl: &Gl, name: &str, val: (f32, f32, f32)) -> Result<(), String> {
-        match self.get_uniform_id(gl, name) {
-            Ok(id) => {
-                self.enable(gl);
-                unsafe {
-                    gl.Uniform3f(id, val.0, val.1, val.2);
-                }
-                Ok(())
+
+    /// The slot remembering the last `T` set to `name`. Setting a different type to the same
+    /// name replaces the slot, so only the newest type is replayed.
+    fn last_value<T: UniformValue>(&self, name: &str) -> Rc<LastValue<T>> {
+        let mut values = self.program.values.borrow_mut();
+        if let Some(slot) = values.get(name) {
+            if let Ok(last_value) = Rc::clone(slot).as_any().downcast::<LastValue<T>>() {
+                return last_value;
             }
-            Err(err) => Err(err),
         }
+        let last_value = Rc::new(LastValue::default());
+        values.insert(name.to_string(), last_value.clone());
+        last_value
     }
-    fn get_vec3(&self, gl: &Gl, name: &str) -> Result<Vec3, String> {
-        match self.get_uniform_id(gl, name) {
-            Ok(id) => {
-                self.enable(gl);
-                unsafe {
-                    let params: *mut GLfloat = [0.0_f32, 0.0_f32, 0.0_f32].as_mut_ptr();
-                    gl.GetUniformfv(self.program_id, id, params);
-                    let x = *params;
-                    let y = *params.add(1);
-                    let z = *params.add(2);
-                    Ok(Vec3 { x, y, z })
-                }
-            }
-            Err(err) => Err(err),
+}
+
+/// Fails if an attribute active in both `old` and `new` is at a different location in `new`.
+fn check_attributes(old: &Reflection, new: &Reflection) -> Result<(), ShaderError> {
+    for attribute in new.attributes() {
+        let moved_from = old
+            .attributes()
+            .iter()
+            .find(|old| old.name == attribute.name && old.location != attribute.location);
+        if let Some(old) = moved_from {
+            return Err(ShaderError::AttributeMoved {
+                name: attribute.name.clone(),
+                from: old.location,
+                to: attribute.location,
+            });
         }
     }
-    fn set_mat4(&self, gl: &Gl, name: &str, val: Mat4) -> Result<(), String> {
-        match self.get_uniform_id(gl, name) {
-            Ok(id) => {
-                self.enable(gl);
-                unsafe {
-                    gl.UniformMatrix4fv(id, 1, gl::FALSE, val.as_ref().as_ptr());
+    Ok(())
+}
+
+/// Links a program from its stages, also returning every file the stages were built from.
+fn link_program(
+    gl: &Gl,
+    stages: &[(ShaderStage, &Path)],
+    preprocessor: &Preprocessor,
+) -> Result<(GLuint, Vec<PathBuf>), ShaderError> {
+    let mut sources = vec![];
+    let mut shaders = vec![];
+    for &(stage, path) in stages {
+        match compile_stage(gl, stage, path, preprocessor, &mut sources) {
+            Ok(shader) => shaders.push(shader),
+            Err(err) => {
+                for shader in shaders {
+                    unsafe { gl.DeleteShader(shader) };
                 }
-                Ok(())
+                return Err(err);
             }
-            Err(err) => Err(err),
         }
     }
-}
 
-impl Shader {
-    pub fn new(gl: &Gl, vertex_path: &str, fragment_path: &str) -> Self {
-        let vertex_shader_source = fs::read(vertex_path).unwrap();
-        let vertex_shader =
-            unsafe { create_shader(gl, gl::VERTEX_SHADER, &add_null_term(&vertex_shader_source)) };
-
-        let fragment_shader_source = fs::read(fragment_path).unwrap();
-        let fragment_shader = unsafe {
-            create_shader(
-                gl,
-                gl::FRAGMENT_SHADER,
-                &add_null_term(&fragment_shader_source),
-            )
-        };
-
-        let program_id = unsafe { gl.CreateProgram() };
-        unsafe {
-            gl.AttachShader(program_id, vertex_shader);
-            gl.AttachShader(program_id, fragment_shader);
-            gl.LinkProgram(program_id);
-            gl.DeleteShader(vertex_shader);
-            gl.DeleteShader(fragment_shader);
-        };
-
-        Self { program_id }
+    let program_id = unsafe { gl.CreateProgram() };
+    let mut status = GLint::from(gl::FALSE);
+    unsafe {
+        for &shader in &shaders {
+            gl.AttachShader(program_id, shader);
+        }
+        gl.LinkProgram(program_id);
+        for shader in shaders {
+            gl.DeleteShader(shader);
+        }
+        gl.GetProgramiv(program_id, gl::LINK_STATUS, &mut status);
+    };
+
+    if status == GLint::from(gl::FALSE) {
+        let log = unsafe { get_program_info_log(gl, program_id) };
+        unsafe { gl.DeleteProgram(program_id) };
+        return Err(ShaderError::Link {
+            paths: stages.iter().map(|(_, path)| path.into()).collect(),
+            messages: parse_info_log(&log),
+        });
     }
 
-    fn get_uniform_id(&self, gl: &Gl, name: &str) -> Result<i32, String> {
-        let uniform_id = unsafe {
-            gl.GetUniformLocation(
-                self.program_id,
-                add_null_term(name.as_bytes()).as_ptr().cast(),
-            )
-        };
+    Ok((program_id, sources))
+}
 
-        if uniform_id == -1 {
-            return Err(String::from("Uniform ID was returned as -1"));
-        }
+fn modified_times(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok())
+        .collect()
+}
 
-        Ok(uniform_id)
+/// Preprocesses and compiles a single stage, mapping the driver's errors back to lines of the
+/// files it was built from. These files are added to `sources`.
+fn compile_stage(
+    gl: &Gl,
+    stage: ShaderStage,
+    path: &Path,
+    preprocessor: &Preprocessor,
+    sources: &mut Vec<PathBuf>,
+) -> Result<GLuint, ShaderError> {
+    let source = preprocessor.process(path)?;
+    for file in source.paths() {
+        if !sources.iter().any(|known| known == file) {
+            sources.push(file.into());
+        }
     }
+
+    unsafe { create_shader(gl, stage.gl_type(), &add_null_term(source.code.as_bytes())) }.map_err(
+        |log| {
+            let mut messages = parse_info_log(&log);
+            for message in &mut messages {
+                source.locate(message);
+            }
+            ShaderError::Compile {
+                stage,
+                path: path.into(),
+                messages,
+            }
+        },
+    )
 }
 
 pub trait DrawableShader {
     fn model(&self) -> &Uniform<Mat4>;
-    fn view(&self) -> &Uniform<Mat4>;
-    fn projection(&self) -> &Uniform<Mat4>;
     fn shader(&self) -> &Shader;
 }
 
 pub struct LightCasterShader {
     pub shader: Shader,
     pub model: Uniform<Mat4>,
-    pub view: Uniform<Mat4>,
-    pub projection: Uniform<Mat4>,
+    pub material: MaterialUniforms,
+    pub shadow_map: Uniform<TextureUnit>,
+    pub point_shadow_maps: Uniform<TextureUnit>,
+    pub spot_shadow_atlas: Uniform<TextureUnit>,
+    pub ssao: Uniform<TextureUnit>,
+}
+
+/// Handles to the `material` samplers of `LightCasterShader`, along with the buffer behind its
+/// `MaterialBlock`.
+pub struct MaterialUniforms {
+    pub samplers: MaterialSamplersUniforms,
+    pub block: UniformBuffer<MaterialBlock>,
+}
+
+// Texture units the `material` samplers of `LightCasterShader` read from.
+pub const DIFFUSE_UNIT: GLuint = 0;
+pub const SPECULAR_UNIT: GLuint = 1;
+pub const AMBIENT_OCCLUSION_UNIT: GLuint = 5;
+
+/// The texture units of the `Material` struct's samplers.
+#[derive(Debug, Clone, Copy, ShaderUniforms)]
+pub struct MaterialSamplers {
+    pub diffuse: TextureUnit,
+    pub specular: TextureUnit,
+    #[uniform(rename = "ambientOcclusion")]
+    pub ambient_occlusion: TextureUnit,
+}
+
+/// The `MaterialBlock` uniform block, set before drawing each mesh.
+#[derive(Clone, Copy, Std140, BufferBlock)]
+#[repr(C)]
+#[block(binding = MATERIAL_BINDING)]
+pub struct MaterialBlock {
+    pub shininess: f32,
+    #[uniform(skip)]
+    _padding: [f32; 3],
+}
+
+impl MaterialBlock {
+    pub fn new(shininess: f32) -> Self {
+        Self {
+            shininess,
+            _padding: [0.0; 3],
+        }
+    }
 }
 
 impl LightCasterShader {
-    pub fn new(gl: &Gl) -> Self {
+    pub fn new(gl: &Gl) -> Result<Self, ShaderError> {
         let shader = Shader::new(
             gl,
             "src/shader/light_casters_vert.glsl",
             "src/shader/light_casters_frag.glsl",
-        );
-        let model = Uniform::new(gl, &shader, "model");
-        let view = Uniform::new(gl, &shader, "view");
-        let projection = Uniform::new(gl, &shader, "projection");
-        Self {
+        )?;
+        shader.check_block::<CameraBlock>()?;
+        shader.check_block::<LightsBlock>()?;
+        shader.check_storage_block::<DirectionLightsBlock>()?;
+        shader.check_storage_block::<SpotLightsBlock>()?;
+        shader.check_storage_block::<PointLightsBlock>()?;
+        shader.check_block::<MaterialBlock>()?;
+        shader.check_block::<ShadowBlock>()?;
+        shader.check_block::<BloomBlock>()?;
+
+        let model = Uniform::new(gl, &shader, "model")?;
+        let material = MaterialUniforms {
+            samplers: MaterialSamplers::uniforms(gl, &shader, "material")?,
+            block: UniformBuffer::new(gl, MaterialBlock::new(0.0)),
+        };
+        MaterialSamplers {
+            diffuse: TextureUnit(DIFFUSE_UNIT),
+            specular: TextureUnit(SPECULAR_UNIT),
+            ambient_occlusion: TextureUnit(AMBIENT_OCCLUSION_UNIT),
+        }
+        .upload(&material.samplers);
+        let shadow_map = Uniform::new(gl, &shader, "shadowMap")?;
+        shadow_map.set(TextureUnit(DIRECTIONAL_SHADOW_UNIT));
+        let point_shadow_maps = Uniform::new(gl, &shader, "pointShadowMaps")?;
+        point_shadow_maps.set(TextureUnit(POINT_SHADOW_UNIT));
+        let spot_shadow_atlas = Uniform::new(gl, &shader, "spotShadowAtlas")?;
+        spot_shadow_atlas.set(TextureUnit(SPOT_SHADOW_UNIT));
+        let ssao = Uniform::new(gl, &shader, "ssao")?;
+        ssao.set(TextureUnit(SSAO_UNIT));
+        Ok(Self {
             shader,
             model,
-            view,
-            projection,
-        }
+            material,
+            shadow_map,
+            point_shadow_maps,
+            spot_shadow_atlas,
+            ssao,
+        })
     }
 }
 pub struct LightCubeShader {
     pub shader: Shader,
+    pub light_color: Uniform<Vec3>,
     model: Uniform<Mat4>,
-    view: Uniform<Mat4>,
-    projection: Uniform<Mat4>,
 }
 
 impl LightCubeShader {
-    pub fn new(gl: &Gl) -> Self {
+    pub fn new(gl: &Gl) -> Result<Self, ShaderError> {
         let shader = Shader::new(
             gl,
             "src/shader/light_cube_vert.glsl",
             "src/shader/light_cube_frag.glsl",
-        );
-        let model = Uniform::new(gl, &shader, "model");
-        let view = Uniform::new(gl, &shader, "view");
-        let projection = Uniform::new(gl, &shader, "projection");
-        Self {
+        )?;
+        shader.check_block::<CameraBlock>()?;
+        shader.check_block::<BloomBlock>()?;
+
+        let light_color = Uniform::new(gl, &shader, "lightColor")?;
+        let model = Uniform::new(gl, &shader, "model")?;
+        Ok(Self {
             shader,
+            light_color,
             model,
-            view,
-            projection,
-        }
+        })
     }
 }
 
-impl DrawableShader for LightCubeShader {
+/// Writes only depth, as seen through `light_space`. Used to render shadow maps.
+pub struct DepthShader {
+    pub shader: Shader,
+    pub light_space: Uniform<Mat4>,
+    model: Uniform<Mat4>,
+}
+
+impl DepthShader {
+    pub fn new(gl: &Gl) -> Result<Self, ShaderError> {
+        let shader = Shader::new(
+            gl,
+            "src/shader/shadow_depth_vert.glsl",
+            "src/shader/shadow_depth_frag.glsl",
+        )?;
+
+        let light_space = Uniform::new(gl, &shader, "lightSpaceMatrix")?;
+        let model = Uniform::new(gl, &shader, "model")?;
+        Ok(Self {
+            shader,
+            light_space,
+            model,
+        })
+    }
+}
+
+impl DrawableShader for DepthShader {
     fn model(&self) -> &Uniform<Mat4> {
         &self.model
     }
-    fn view(&self) -> &Uniform<Mat4> {
-        &self.view
-    }
-    fn projection(&self) -> &Uniform<Mat4> {
-        &self.projection
-    }
     fn shader(&self) -> &Shader {
         &self.shader
     }
 }
 
-impl DrawableShader for LightCasterShader {
-    fn model(&self) -> &Uniform<Mat4> {
-        &self.model
+/// Writes the distance to a point light as depth, into all six faces of one cube of a cube map
+/// array at once.
+pub struct PointDepthShader {
+    pub shader: Shader,
+    pub light_pos: Uniform<Vec3>,
+    pub far_plane: Uniform<f32>,
+    /// View projection matrix of each face, see `cube_face_matrices`.
+    pub face_matrices: Uniform<[Mat4; 6]>,
+    pub layer: Uniform<i32>,
+    model: Uniform<Mat4>,
+}
+
+impl PointDepthShader {
+    pub fn new(gl: &Gl) -> Result<Self, ShaderError> {
+        let shader = Shader::with_geometry(
+            gl,
+            "src/shader/point_shadow_vert.glsl",
+            "src/shader/point_shadow_geom.glsl",
+            "src/shader/point_shadow_frag.glsl",
+        )?;
+
+        Ok(Self {
+            light_pos: Uniform::new(gl, &shader, "lightPos")?,
+            far_plane: Uniform::new(gl, &shader, "farPlane")?,
+            face_matrices: Uniform::new(gl, &shader, "faceMatrices")?,
+            layer: Uniform::new(gl, &shader, "shadowLayer")?,
+            model: Uniform::new(gl, &shader, "model")?,
+            shader,
+        })
     }
-    fn view(&self) -> &Uniform<Mat4> {
-        &self.view
+}
+
+/// Runs one `PostEffect` over `screen_texture`, drawn as a full screen triangle.
+pub struct PostProcessShader {
+    pub shader: Shader,
+    pub screen_texture: Uniform<TextureUnit>,
+    pub color_lut: Uniform<TextureUnit>,
+    pub effect: Uniform<i32>,
+    pub kernel: Uniform<Mat3>,
+    /// Radius, softness and strength of the vignette.
+    pub vignette: Uniform<Vec3>,
+    pub grade_strength: Uniform<f32>,
+}
+
+impl PostProcessShader {
+    pub fn new(gl: &Gl) -> Result<Self, ShaderError> {
+        let preprocessor = EFFECT_DEFINES
+            .iter()
+            .fold(Preprocessor::new(), |preprocessor, (name, effect)| {
+                preprocessor.define(name, effect)
+            });
+        let shader = Shader::with_preprocessor(
+            gl,
+            "src/shader/post_process_vert.glsl",
+            "src/shader/post_process_frag.glsl",
+            preprocessor,
+        )?;
+
+        Ok(Self {
+            screen_texture: Uniform::new(gl, &shader, "screenTexture")?,
+            color_lut: Uniform::new(gl, &shader, "colorLut")?,
+            effect: Uniform::new(gl, &shader, "effect")?,
+            kernel: Uniform::new(gl, &shader, "kernel")?,
+            vignette: Uniform::new(gl, &shader, "vignette")?,
+            grade_strength: Uniform::new(gl, &shader, "gradeStrength")?,
+            shader,
+        })
     }
-    fn projection(&self) -> &Uniform<Mat4> {
-        &self.projection
+}
+
+/// Tone maps `hdr_texture` with one of the `ToneMapOperator`s, drawn as a full screen triangle.
+pub struct ToneMapShader {
+    pub shader: Shader,
+    pub hdr_texture: Uniform<TextureUnit>,
+    pub bloom: Uniform<TextureUnit>,
+    pub bloom_intensity: Uniform<f32>,
+    pub luminance: Uniform<TextureUnit>,
+    pub operator: Uniform<i32>,
+    pub exposure: Uniform<f32>,
+    pub auto_exposure: Uniform<bool>,
+}
+
+impl ToneMapShader {
+    pub fn new(gl: &Gl) -> Result<Self, ShaderError> {
+        let preprocessor = TONE_MAP_DEFINES
+            .iter()
+            .fold(Preprocessor::new(), |preprocessor, (name, operator)| {
+                preprocessor.define(name, operator)
+            });
+        let shader = Shader::with_preprocessor(
+            gl,
+            "src/shader/post_process_vert.glsl",
+            "src/shader/tone_map_frag.glsl",
+            preprocessor,
+        )?;
+
+        Ok(Self {
+            hdr_texture: Uniform::new(gl, &shader, "hdrTexture")?,
+            bloom: Uniform::new(gl, &shader, "bloom")?,
+            bloom_intensity: Uniform::new(gl, &shader, "bloomIntensity")?,
+            luminance: Uniform::new(gl, &shader, "luminance")?,
+            operator: Uniform::new(gl, &shader, "toneMapOperator")?,
+            exposure: Uniform::new(gl, &shader, "exposure")?,
+            auto_exposure: Uniform::new(gl, &shader, "autoExposure")?,
+            shader,
+        })
     }
-    fn shader(&self) -> &Shader {
-        &self.shader
+}
+
+/// Writes the log luminance of `hdr_texture`, for auto exposure.
+pub struct LuminanceShader {
+    pub shader: Shader,
+    pub hdr_texture: Uniform<TextureUnit>,
+}
+
+impl LuminanceShader {
+    pub fn new(gl: &Gl) -> Result<Self, ShaderError> {
+        let shader = Shader::new(
+            gl,
+            "src/shader/post_process_vert.glsl",
+            "src/shader/luminance_frag.glsl",
+        )?;
+
+        Ok(Self {
+            hdr_texture: Uniform::new(gl, &shader, "hdrTexture")?,
+            shader,
+        })
     }
 }
 
-pub struct Uniform<T> {
-    gl: Gl,
-    shader_id: u32,
-    uniform_id: i32,
-    resource_type: PhantomData<T>,
+/// Downsamples `source` into a half as large level of the bloom chain.
+pub struct BloomDownsampleShader {
+    pub shader: Shader,
+    pub source: Uniform<TextureUnit>,
 }
 
-impl<T> Uniform<T> {
-    pub fn new(gl: &Gl, shader: &Shader, name: &str) -> Self {
-        let uniform_id = shader.get_uniform_id(gl, name).unwrap();
-        Self {
-            gl: gl.clone(),
-            shader_id: shader.program_id,
-            uniform_id,
-            resource_type: PhantomData,
-        }
+impl BloomDownsampleShader {
+    pub fn new(gl: &Gl) -> Result<Self, ShaderError> {
+        let shader = Shader::new(
+            gl,
+            "src/shader/post_process_vert.glsl",
+            "src/shader/bloom_downsample_frag.glsl",
+        )?;
+
+        Ok(Self {
+            source: Uniform::new(gl, &shader, "source")?,
+            shader,
+        })
     }
 }
 
-pub trait UniformGetSet<T> {
-    fn get(&self) -> T;
-    fn set(&self, val: T);
+/// Blurs `source` into a twice as large level of the bloom chain.
+pub struct BloomUpsampleShader {
+    pub shader: Shader,
+    pub source: Uniform<TextureUnit>,
+    pub filter_radius: Uniform<f32>,
 }
 
-impl UniformGetSet<Vec3> for Uniform<Vec3> {
-    fn get(&self) -> Vec3 {
-        unsafe {
-            let params: *mut f32 = [0.0, 0.0, 0.0].as_mut_ptr();
-            self.gl
-                .GetUniformfv(self.shader_id, self.uniform_id, params);
-            let x = *params.add(0);
-            let y = *params.add(1);
-            let z = *params.add(2);
-
-            Vec3 { x, y, z }
-        }
+impl BloomUpsampleShader {
+    pub fn new(gl: &Gl) -> Result<Self, ShaderError> {
+        let shader = Shader::new(
+            gl,
+            "src/shader/post_process_vert.glsl",
+            "src/shader/bloom_upsample_frag.glsl",
+        )?;
+
+        Ok(Self {
+            source: Uniform::new(gl, &shader, "source")?,
+            filter_radius: Uniform::new(gl, &shader, "filterRadius")?,
+            shader,
+        })
     }
-    fn set(&self, val: Vec3) {
-        unsafe {
-            self.gl
-                .ProgramUniform3f(self.shader_id, self.uniform_id, val.x, val.y, val.z)
-        }
+}
+
+/// Writes view space normals, and depth, of the lit geometry for `Ssao`.
+pub struct GBufferShader {
+    pub shader: Shader,
+    model: Uniform<Mat4>,
+}
+
+impl GBufferShader {
+    pub fn new(gl: &Gl) -> Result<Self, ShaderError> {
+        let shader = Shader::new(
+            gl,
+            "src/shader/gbuffer_vert.glsl",
+            "src/shader/gbuffer_frag.glsl",
+        )?;
+        shader.check_block::<CameraBlock>()?;
+
+        Ok(Self {
+            model: Uniform::new(gl, &shader, "model")?,
+            shader,
+        })
     }
 }
 
-impl UniformGetSet<f32> for Uniform<f32> {
-    fn get(&self) -> f32 {
-        unsafe {
-            let params: *mut f32 = [0.0].as_mut_ptr();
-            self.gl
-                .GetUniformfv(self.shader_id, self.uniform_id, params);
-            *params
-        }
+/// Writes how much of the ambient light reaches each fragment of the G-buffer.
+pub struct SsaoShader {
+    pub shader: Shader,
+    pub normal: Uniform<TextureUnit>,
+    pub depth: Uniform<TextureUnit>,
+    pub noise: Uniform<TextureUnit>,
+    pub samples: Uniform<[Vec3; SSAO_KERNEL_SIZE]>,
+    pub inverse_projection: Uniform<Mat4>,
+    pub radius: Uniform<f32>,
+    pub bias: Uniform<f32>,
+}
+
+impl SsaoShader {
+    pub fn new(gl: &Gl) -> Result<Self, ShaderError> {
+        let shader = Shader::with_preprocessor(
+            gl,
+            "src/shader/post_process_vert.glsl",
+            "src/shader/ssao_frag.glsl",
+            ssao_preprocessor(),
+        )?;
+        shader.check_block::<CameraBlock>()?;
+
+        Ok(Self {
+            normal: Uniform::new(gl, &shader, "gNormal")?,
+            depth: Uniform::new(gl, &shader, "gDepth")?,
+            noise: Uniform::new(gl, &shader, "noise")?,
+            samples: Uniform::new(gl, &shader, "samples")?,
+            inverse_projection: Uniform::new(gl, &shader, "inverseProjection")?,
+            radius: Uniform::new(gl, &shader, "radius")?,
+            bias: Uniform::new(gl, &shader, "bias")?,
+            shader,
+        })
     }
-    fn set(&self, val: f32) {
-        unsafe {
-            self.gl
-                .ProgramUniform1f(self.shader_id, self.uniform_id, val)
-        }
+}
+
+/// Blurs the occlusion in `source` over the size of the SSAO noise texture.
+pub struct SsaoBlurShader {
+    pub shader: Shader,
+    pub source: Uniform<TextureUnit>,
+}
+
+impl SsaoBlurShader {
+    pub fn new(gl: &Gl) -> Result<Self, ShaderError> {
+        let shader = Shader::with_preprocessor(
+            gl,
+            "src/shader/post_process_vert.glsl",
+            "src/shader/ssao_blur_frag.glsl",
+            ssao_preprocessor(),
+        )?;
+
+        Ok(Self {
+            source: Uniform::new(gl, &shader, "source")?,
+            shader,
+        })
     }
 }
 
-impl UniformGetSet<Mat4> for Uniform<Mat4> {
-    fn get(&self) -> Mat4 {
-        unsafe {
-            let mut params: [f32; 16] = zeroed();
-            let params_ptr: *mut f32 = params.as_mut_ptr();
-            self.gl
-                .GetUniformfv(self.shader_id, self.uniform_id, params_ptr);
-            Mat4::from_cols_array(&params)
-        }
+fn ssao_preprocessor() -> Preprocessor {
+    SSAO_DEFINES
+        .iter()
+        .fold(Preprocessor::new(), |preprocessor, (name, value)| {
+            preprocessor.define(name, value)
+        })
+}
+
+impl DrawableShader for GBufferShader {
+    fn model(&self) -> &Uniform<Mat4> {
+        &self.model
     }
-    fn set(&self, val: Mat4) {
-        const COUNT: GLsizei = 1;
-        unsafe {
-            self.gl.ProgramUniformMatrix4fv(
-                self.shader_id,
-                self.uniform_id,
-                COUNT,
-                gl::FALSE,
-                val.as_ref().as_ptr(),
-            )
-        }
+    fn shader(&self) -> &Shader {
+        &self.shader
+    }
+}
+
+impl DrawableShader for PointDepthShader {
+    fn model(&self) -> &Uniform<Mat4> {
+        &self.model
+    }
+    fn shader(&self) -> &Shader {
+        &self.shader
+    }
+}
+
+impl DrawableShader for LightCubeShader {
+    fn model(&self) -> &Uniform<Mat4> {
+        &self.model
+    }
+    fn shader(&self) -> &Shader {
+        &self.shader
+    }
+}
+
+impl DrawableShader for LightCasterShader {
+    fn model(&self) -> &Uniform<Mat4> {
+        &self.model
+    }
+    fn shader(&self) -> &Shader {
+        &self.shader
     }
 }
@@ -0,0 +1,382 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    marker::PhantomData,
+    ptr,
+};
+
+use glam::{IVec2, IVec3, IVec4, Mat4, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
+
+use crate::gl::{
+    self,
+    types::{GLint, GLsizeiptr, GLuint},
+    Gl,
+};
+
+use super::{BlockError, Reflection};
+
+// Binding points of the blocks shared by every program, matching the `binding` layout
+// qualifiers of their GLSL declarations.
+pub const CAMERA_BINDING: GLuint = 0;
+pub const LIGHTS_BINDING: GLuint = 1;
+pub const MATERIAL_BINDING: GLuint = 2;
+pub const SHADOW_BINDING: GLuint = 3;
+pub const BLOOM_BINDING: GLuint = 4;
+// Storage blocks have binding points of their own.
+pub const POINT_LIGHTS_BINDING: GLuint = 0;
+pub const SPOT_LIGHTS_BINDING: GLuint = 1;
+pub const DIRECTION_LIGHTS_BINDING: GLuint = 2;
+
+/// A type laid out in Rust exactly like the std140 layout lays it out in a uniform block, so
+/// it can be copied into a buffer as is. Structs get it through `#[derive(Std140)]`, which
+/// checks every field's offset at compile time.
+///
+/// # Safety
+/// `ALIGN` must be the type's std140 base alignment, and its size must be the size std140
+/// gives it.
+pub unsafe trait Std140: Copy + 'static {
+    const ALIGN: usize;
+    /// GLSL name and byte offset of each member of a struct, checked against the shader's
+    /// reflection. Fields are renamed with `#[uniform(rename = "...")]`.
+    const MEMBERS: &'static [(&'static str, usize)] = &[];
+}
+
+macro_rules! std140 {
+    ($($type:ty => $align:expr),* $(,)?) => {
+        $(unsafe impl Std140 for $type {
+            const ALIGN: usize = $align;
+        })*
+    };
+}
+
+// `bool` is 4 bytes in std140, so it's left out in favor of `u32`. `Mat3` is left out since
+// std140 pads each of its columns to a `vec4`.
+std140!(
+    f32 => 4, i32 => 4, u32 => 4,
+    Vec2 => 8, IVec2 => 8, UVec2 => 8,
+    Vec3 => 16, IVec3 => 16, UVec3 => 16,
+    Vec4 => 16, IVec4 => 16, UVec4 => 16,
+    Mat4 => 16,
+);
+
+/// std140 pads every array element to 16 bytes, so only elements whose size is already a
+/// multiple of that have the same stride in Rust.
+unsafe impl<T: Std140, const N: usize> Std140 for [T; N] {
+    const ALIGN: usize = {
+        assert!(
+            size_of::<T>().is_multiple_of(16),
+            "std140 array elements must be a multiple of 16 bytes"
+        );
+        16
+    };
+}
+
+/// A `Std140` struct holding a whole uniform block, usually through `#[derive(BufferBlock)]`.
+/// ```
+/// use glam::{Mat4, Vec3};
+/// use learn_ogl_rs::shader::{BufferBlock, Std140};
+///
+/// #[derive(Clone, Copy, Std140, BufferBlock)]
+/// #[repr(C)]
+/// #[block(name = "Sky", binding = 3)]
+/// struct SkyBlock {
+///     #[uniform(rename = "sunDir")]
+///     sun_dir: Vec3,
+///     intensity: f32,
+///     rotation: Mat4,
+/// }
+///
+/// assert_eq!(SkyBlock::NAME, "Sky");
+/// assert_eq!(SkyBlock::MEMBERS, [("sunDir", 0), ("intensity", 12), ("rotation", 16)]);
+/// ```
+pub trait BufferBlock: Std140 {
+    /// Name of the block in GLSL.
+    const NAME: &'static str;
+    const BINDING: GLuint;
+}
+
+/// A shader storage block holding nothing but a runtime-sized array of `Element`s, like
+/// `buffer Lights { Light lights[]; }`. std430 lays out structs of vectors and floats the
+/// same way std140 does, so elements are checked as `Std140`.
+pub trait StorageArray {
+    type Element: Std140;
+    /// Name of the block in GLSL.
+    const NAME: &'static str;
+    const BINDING: GLuint;
+}
+
+/// Checks the block `T` is bound to against a program's reflection: its binding, size and the
+/// offset of every member.
+pub fn check_layout<T: BufferBlock>(reflection: &Reflection) -> Result<(), BlockError> {
+    let block = reflection
+        .block(T::NAME)
+        .ok_or_else(|| BlockError::NotFound(T::NAME.to_string()))?;
+    if block.binding != T::BINDING {
+        return Err(BlockError::BindingMismatch {
+            block: T::NAME.to_string(),
+            expected: T::BINDING,
+            found: block.binding,
+        });
+    }
+    if block.data_size as usize > size_of::<T>() {
+        return Err(BlockError::SizeMismatch {
+            block: T::NAME.to_string(),
+            expected: size_of::<T>(),
+            found: block.data_size,
+        });
+    }
+
+    // Structs and arrays are reported one basic type at a time, the member starts at the
+    // lowest offset of them.
+    let mut offsets: HashMap<&str, GLint> = HashMap::new();
+    for uniform in reflection.uniforms() {
+        if uniform.block_index != Some(block.index) {
+            continue;
+        }
+        let name = uniform
+            .name
+            .strip_prefix(T::NAME)
+            .and_then(|name| name.strip_prefix('.'))
+            .unwrap_or(&uniform.name);
+        let member = name.split(['.', '[']).next().unwrap_or(name);
+        let offset = uniform.offset.unwrap_or(0);
+        offsets
+            .entry(member)
+            .and_modify(|lowest| *lowest = (*lowest).min(offset))
+            .or_insert(offset);
+    }
+
+    check_members(T::NAME, offsets, T::MEMBERS)
+}
+
+/// Checks the storage block `T` is bound to against a program's reflection: its binding, the
+/// stride of its array and the offset of every member of its elements.
+pub fn check_storage_layout<T: StorageArray>(reflection: &Reflection) -> Result<(), BlockError> {
+    let block = reflection
+        .storage_block(T::NAME)
+        .ok_or_else(|| BlockError::NotFound(T::NAME.to_string()))?;
+    if block.binding != T::BINDING {
+        return Err(BlockError::BindingMismatch {
+            block: T::NAME.to_string(),
+            expected: T::BINDING,
+            found: block.binding,
+        });
+    }
+    let expected = size_of::<T::Element>();
+    match block.array_stride {
+        Some(stride) if stride as usize == expected => {}
+        found => {
+            return Err(BlockError::StrideMismatch {
+                block: T::NAME.to_string(),
+                expected,
+                found: found.unwrap_or(0),
+            })
+        }
+    }
+
+    // Members are reported like `lights[0].position`, and like uniform block members one
+    // basic type at a time.
+    let mut offsets: HashMap<&str, GLint> = HashMap::new();
+    for (name, offset) in &block.members {
+        let name = name
+            .split_once("].")
+            .map_or(name.as_str(), |(_, name)| name);
+        let member = name.split(['.', '[']).next().unwrap_or(name);
+        offsets
+            .entry(member)
+            .and_modify(|lowest| *lowest = (*lowest).min(*offset))
+            .or_insert(*offset);
+    }
+    check_members(T::NAME, offsets, T::Element::MEMBERS)
+}
+
+/// Compares the lowest offset found for each member of `block` against the Rust struct's.
+fn check_members(
+    block: &str,
+    mut offsets: HashMap<&str, GLint>,
+    members: &[(&str, usize)],
+) -> Result<(), BlockError> {
+    for (member, expected) in members {
+        let Some(found) = offsets.remove(*member) else {
+            return Err(BlockError::MissingMember {
+                block: block.to_string(),
+                member: member.to_string(),
+            });
+        };
+        if found as usize != *expected {
+            return Err(BlockError::OffsetMismatch {
+                block: block.to_string(),
+                member: member.to_string(),
+                expected: *expected,
+                found,
+            });
+        }
+    }
+    match offsets.into_keys().min() {
+        Some(member) => Err(BlockError::Unbound {
+            block: block.to_string(),
+            member: member.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// The buffer behind every program's `T` block, bound to `T::BINDING` for as long as it
+/// lives. Changes made with `update` are uploaded on the next `flush`.
+pub struct UniformBuffer<T: BufferBlock> {
+    gl: Gl,
+    id: GLuint,
+    data: RefCell<T>,
+    dirty: Cell<bool>,
+}
+
+impl<T: BufferBlock> UniformBuffer<T> {
+    pub fn new(gl: &Gl, data: T) -> Self {
+        let mut id: GLuint = 0;
+        unsafe {
+            gl.CreateBuffers(1, &mut id);
+            gl.NamedBufferStorage(
+                id,
+                size_of::<T>() as GLsizeiptr,
+                ptr::from_ref(&data).cast(),
+                gl::DYNAMIC_STORAGE_BIT,
+            );
+            gl.BindBufferBase(gl::UNIFORM_BUFFER, T::BINDING, id);
+        }
+        Self {
+            gl: gl.clone(),
+            id,
+            data: RefCell::new(data),
+            dirty: Cell::new(false),
+        }
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    pub fn get(&self) -> T {
+        *self.data.borrow()
+    }
+
+    /// Replaces the whole block and uploads it right away, for blocks that change between
+    /// draw calls.
+    pub fn set(&self, data: T) {
+        self.data.replace(data);
+        self.dirty.set(false);
+        self.upload();
+    }
+
+    pub fn update(&self, update: impl FnOnce(&mut T)) {
+        update(&mut self.data.borrow_mut());
+        self.dirty.set(true);
+    }
+
+    /// Uploads the changes made with `update` since the last flush.
+    pub fn flush(&self) {
+        if self.dirty.replace(false) {
+            self.upload();
+        }
+    }
+
+    fn upload(&self) {
+        let data = self.data.borrow();
+        unsafe {
+            self.gl.NamedBufferSubData(
+                self.id,
+                0,
+                size_of::<T>() as GLsizeiptr,
+                ptr::from_ref(&*data).cast(),
+            );
+        }
+    }
+}
+
+impl<T: BufferBlock> Drop for UniformBuffer<T> {
+    fn drop(&mut self) {
+        unsafe { self.gl.DeleteBuffers(1, &self.id) };
+    }
+}
+
+/// The buffer behind every program's `T` storage block, bound to `T::BINDING` for as long as it
+/// lives. It grows as needed, so shaders need to be told how many elements were uploaded.
+pub struct StorageBuffer<T: StorageArray> {
+    gl: Gl,
+    id: GLuint,
+    /// Number of elements the buffer has room for.
+    capacity: usize,
+    len: usize,
+    block: PhantomData<T>,
+}
+
+impl<T: StorageArray> StorageBuffer<T> {
+    const MIN_CAPACITY: usize = 16;
+
+    pub fn new(gl: &Gl) -> Self {
+        let mut buffer = Self {
+            gl: gl.clone(),
+            id: 0,
+            capacity: 0,
+            len: 0,
+            block: PhantomData,
+        };
+        buffer.allocate(Self::MIN_CAPACITY);
+        buffer
+    }
+
+    pub fn id(&self) -> GLuint {
+        self.id
+    }
+
+    /// Number of elements uploaded by the last `upload`.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Replaces the contents of the buffer with `elements`, reallocating it if they don't fit.
+    pub fn upload(&mut self, elements: &[T::Element]) {
+        if elements.len() > self.capacity {
+            self.allocate(elements.len().next_power_of_two());
+        }
+        self.len = elements.len();
+        if elements.is_empty() {
+            return;
+        }
+        unsafe {
+            self.gl.NamedBufferSubData(
+                self.id,
+                0,
+                size_of_val(elements) as GLsizeiptr,
+                elements.as_ptr().cast(),
+            );
+        }
+    }
+
+    fn allocate(&mut self, capacity: usize) {
+        let gl = &self.gl;
+        unsafe {
+            if self.id != 0 {
+                gl.DeleteBuffers(1, &self.id);
+            }
+            gl.CreateBuffers(1, &mut self.id);
+            gl.NamedBufferStorage(
+                self.id,
+                (capacity * size_of::<T::Element>()) as GLsizeiptr,
+                ptr::null(),
+                gl::DYNAMIC_STORAGE_BIT,
+            );
+            gl.BindBufferBase(gl::SHADER_STORAGE_BUFFER, T::BINDING, self.id);
+        }
+        self.capacity = capacity;
+    }
+}
+
+impl<T: StorageArray> Drop for StorageBuffer<T> {
+    fn drop(&mut self) {
+        unsafe { self.gl.DeleteBuffers(1, &self.id) };
+    }
+}
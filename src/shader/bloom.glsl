@@ -0,0 +1,12 @@
+#pragma once
+
+// Set by the renderer, see `BloomBlock`.
+layout (std140, binding = 4) uniform BloomBlock {
+    float bloomThreshold;
+};
+
+// The part of `color` that glows, written to the second color attachment of the scene.
+vec4 BloomColor(vec3 color) {
+    float brightness = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    return brightness > bloomThreshold ? vec4(color, 1.0) : vec4(0.0, 0.0, 0.0, 1.0);
+}
@@ -0,0 +1,39 @@
+#version 430 core
+
+in vec2 TexCoords;
+
+out vec3 Downsampled;
+
+// The previous, twice as large level.
+uniform sampler2D source;
+
+// 13 taps around the center, weighted so that neighbouring 2x2 blocks overlap. This keeps
+// small bright spots from flickering as they move between texels.
+void main()
+{
+    vec2 texel = 1.0 / vec2(textureSize(source, 0));
+    float x = texel.x;
+    float y = texel.y;
+
+    vec3 a = texture(source, TexCoords + vec2(-2.0 * x, 2.0 * y)).rgb;
+    vec3 b = texture(source, TexCoords + vec2(0.0, 2.0 * y)).rgb;
+    vec3 c = texture(source, TexCoords + vec2(2.0 * x, 2.0 * y)).rgb;
+
+    vec3 d = texture(source, TexCoords + vec2(-2.0 * x, 0.0)).rgb;
+    vec3 e = texture(source, TexCoords).rgb;
+    vec3 f = texture(source, TexCoords + vec2(2.0 * x, 0.0)).rgb;
+
+    vec3 g = texture(source, TexCoords + vec2(-2.0 * x, -2.0 * y)).rgb;
+    vec3 h = texture(source, TexCoords + vec2(0.0, -2.0 * y)).rgb;
+    vec3 i = texture(source, TexCoords + vec2(2.0 * x, -2.0 * y)).rgb;
+
+    vec3 j = texture(source, TexCoords + vec2(-x, y)).rgb;
+    vec3 k = texture(source, TexCoords + vec2(x, y)).rgb;
+    vec3 l = texture(source, TexCoords + vec2(-x, -y)).rgb;
+    vec3 m = texture(source, TexCoords + vec2(x, -y)).rgb;
+
+    Downsampled = e * 0.125;
+    Downsampled += (a + c + g + i) * 0.03125;
+    Downsampled += (b + d + f + h) * 0.0625;
+    Downsampled += (j + k + l + m) * 0.125;
+}
@@ -0,0 +1,34 @@
+#version 430 core
+
+in vec2 TexCoords;
+
+out vec3 Upsampled;
+
+// The next, half as large level.
+uniform sampler2D source;
+// Texture coordinate offset of the outer taps.
+uniform float filterRadius;
+
+// A 3x3 tent filter, added onto the level being rendered into by blending.
+void main()
+{
+    float x = filterRadius;
+    float y = filterRadius;
+
+    vec3 a = texture(source, TexCoords + vec2(-x, y)).rgb;
+    vec3 b = texture(source, TexCoords + vec2(0.0, y)).rgb;
+    vec3 c = texture(source, TexCoords + vec2(x, y)).rgb;
+
+    vec3 d = texture(source, TexCoords + vec2(-x, 0.0)).rgb;
+    vec3 e = texture(source, TexCoords).rgb;
+    vec3 f = texture(source, TexCoords + vec2(x, 0.0)).rgb;
+
+    vec3 g = texture(source, TexCoords + vec2(-x, -y)).rgb;
+    vec3 h = texture(source, TexCoords + vec2(0.0, -y)).rgb;
+    vec3 i = texture(source, TexCoords + vec2(x, -y)).rgb;
+
+    Upsampled = e * 4.0;
+    Upsampled += (b + d + f + h) * 2.0;
+    Upsampled += (a + c + g + i);
+    Upsampled *= 1.0 / 16.0;
+}
@@ -0,0 +1,8 @@
+#pragma once
+
+// Set once per frame by the renderer, see `CameraBlock`.
+layout (std140, binding = 0) uniform CameraBlock {
+    mat4 view;
+    mat4 projection;
+    vec3 viewPos;
+};
@@ -0,0 +1,438 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use crate::gl::{
+    self,
+    types::{GLenum, GLint, GLuint},
+};
+
+use super::reflection::glsl_type_name;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderStage {
+    Vertex,
+    Geometry,
+    Fragment,
+}
+
+impl ShaderStage {
+    pub fn gl_type(self) -> GLenum {
+        match self {
+            ShaderStage::Vertex => gl::VERTEX_SHADER,
+            ShaderStage::Geometry => gl::GEOMETRY_SHADER,
+            ShaderStage::Fragment => gl::FRAGMENT_SHADER,
+        }
+    }
+}
+
+impl fmt::Display for ShaderStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderStage::Vertex => write!(f, "vertex"),
+            ShaderStage::Geometry => write!(f, "geometry"),
+            ShaderStage::Fragment => write!(f, "fragment"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ShaderError {
+    Io {
+        path: PathBuf,
+        err: io::Error,
+    },
+    Compile {
+        stage: ShaderStage,
+        path: PathBuf,
+        messages: Vec<LogMessage>,
+    },
+    Link {
+        paths: Vec<PathBuf>,
+        messages: Vec<LogMessage>,
+    },
+    /// A malformed directive, see `Preprocessor`.
+    Preprocess {
+        path: PathBuf,
+        line: u32,
+        message: String,
+    },
+    /// Files that include each other, the first file is repeated at the end.
+    IncludeCycle(Vec<PathBuf>),
+    Uniform(UniformError),
+    Block(BlockError),
+    /// A reload would move a vertex attribute, which the vertex arrays set up for the program
+    /// wouldn't follow. Attributes should be pinned with `layout (location = ...)`.
+    AttributeMoved {
+        name: String,
+        from: GLint,
+        to: GLint,
+    },
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderError::Io { path, err } => {
+                write!(f, "Failed to read shader '{}': {err}", path.display())
+            }
+            ShaderError::Compile {
+                stage,
+                path,
+                messages,
+            } => {
+                writeln!(f, "Failed to compile {stage} shader '{}':", path.display())?;
+                messages
+                    .iter()
+                    .try_for_each(|message| write!(f, "{message}"))
+            }
+            ShaderError::Link { paths, messages } => {
+                let paths: Vec<_> = paths
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                writeln!(f, "Failed to link program from {}:", paths.join(", "))?;
+                messages
+                    .iter()
+                    .try_for_each(|message| write!(f, "{message}"))
+            }
+            ShaderError::Preprocess {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{line}: {message}", path.display()),
+            ShaderError::IncludeCycle(paths) => {
+                let paths: Vec<_> = paths
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                write!(f, "Include cycle: {}", paths.join(" -> "))
+            }
+            ShaderError::Uniform(err) => write!(f, "{err}"),
+            ShaderError::Block(err) => write!(f, "{err}"),
+            ShaderError::AttributeMoved { name, from, to } => write!(
+                f,
+                "Attribute '{name}' would move from location {from} to {to}, pin it with a layout qualifier"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+impl From<UniformError> for ShaderError {
+    fn from(err: UniformError) -> Self {
+        ShaderError::Uniform(err)
+    }
+}
+
+impl From<BlockError> for ShaderError {
+    fn from(err: BlockError) -> Self {
+        ShaderError::Block(err)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UniformError {
+    /// Not declared in the shader, optimized out, or declared inside a uniform block.
+    NotFound(String),
+    TypeMismatch {
+        name: String,
+        expected: GLenum,
+        found: GLenum,
+    },
+    /// An array was set to a uniform that isn't one, or is one of a different length.
+    LengthMismatch {
+        name: String,
+        expected: usize,
+        found: GLint,
+    },
+    /// A member of a GLSL struct that the Rust struct bound to it has no field for.
+    Unbound(String),
+}
+
+impl fmt::Display for UniformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UniformError::NotFound(name) => write!(f, "Uniform '{name}' isn't active"),
+            UniformError::TypeMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Uniform '{name}' is a {}, not a {}",
+                glsl_type_name(*found),
+                glsl_type_name(*expected)
+            ),
+            UniformError::LengthMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Uniform '{name}' has {found} element(s), can't set {expected} elements"
+            ),
+            UniformError::Unbound(name) => {
+                write!(f, "Uniform '{name}' has no matching field to be set from")
+            }
+        }
+    }
+}
+
+impl std::error::Error for UniformError {}
+
+/// A Rust type that doesn't match the layout of the uniform or storage block it's bound to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockError {
+    NotFound(String),
+    BindingMismatch {
+        block: String,
+        expected: GLuint,
+        found: GLuint,
+    },
+    /// The block needs more bytes than the Rust struct has.
+    SizeMismatch {
+        block: String,
+        expected: usize,
+        found: GLint,
+    },
+    OffsetMismatch {
+        block: String,
+        member: String,
+        expected: usize,
+        found: GLint,
+    },
+    /// A member of the block that the Rust struct has no field for.
+    Unbound {
+        block: String,
+        member: String,
+    },
+    /// A field of the Rust struct that isn't a member of the block.
+    MissingMember {
+        block: String,
+        member: String,
+    },
+    /// The elements of a storage block's array are a different size than the Rust type.
+    StrideMismatch {
+        block: String,
+        expected: usize,
+        found: GLint,
+    },
+}
+
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockError::NotFound(block) => write!(f, "Block '{block}' isn't active"),
+            BlockError::BindingMismatch {
+                block,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Block '{block}' is bound to {found}, expected binding {expected}"
+            ),
+            BlockError::SizeMismatch {
+                block,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Uniform block '{block}' is {found} bytes, larger than its {expected} byte struct"
+            ),
+            BlockError::OffsetMismatch {
+                block,
+                member,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Member '{member}' of block '{block}' is at offset {found}, not {expected}"
+            ),
+            BlockError::Unbound { block, member } => write!(
+                f,
+                "Member '{member}' of block '{block}' has no matching field"
+            ),
+            BlockError::MissingMember { block, member } => {
+                write!(f, "Block '{block}' has no member '{member}'")
+            }
+            BlockError::StrideMismatch {
+                block,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Elements of storage block '{block}' are {found} bytes apart, not {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BlockError {}
+
+/// One line of a driver's info log. Drivers disagree on how locations are formatted, so the
+/// location is only filled in for the formats `parse_info_log` recognizes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogMessage {
+    /// Index of the source string the message refers to, set by `#line` directives.
+    pub source_string: Option<u32>,
+    pub line: Option<u32>,
+    pub message: String,
+    /// File the source string was read from, see `LogMessage::locate`.
+    pub file: Option<PathBuf>,
+    /// The offending line of source code.
+    pub code: Option<String>,
+}
+
+impl LogMessage {
+    /// Resolves the source string index to one of `sources`, given as file paths and their
+    /// contents.
+    pub fn locate(&mut self, sources: &[(&Path, &str)]) {
+        let Some((path, contents)) = self
+            .source_string
+            .and_then(|index| sources.get(index as usize))
+        else {
+            return;
+        };
+        self.file = Some(path.to_path_buf());
+        self.code = self
+            .line
+            .and_then(|line| contents.lines().nth(line.checked_sub(1)? as usize))
+            .map(|code| code.trim_end().to_string());
+    }
+}
+
+impl fmt::Display for LogMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.file, self.source_string, self.line) {
+            (Some(file), _, Some(line)) => write!(f, "{}:{line}: ", file.display())?,
+            (None, Some(source_string), Some(line)) => write!(f, "{source_string}:{line}: ")?,
+            _ => (),
+        }
+        writeln!(f, "{}", self.message)?;
+        if let (Some(code), Some(line)) = (&self.code, self.line) {
+            writeln!(f, "{line:>6} | {code}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Splits an info log into messages, recognizing the `0:12(5): error: ...` (Mesa),
+/// `0(12) : error C0000: ...` (NVIDIA) and `ERROR: 0:12: ...` (AMD, Intel) formats.
+pub fn parse_info_log(log: &str) -> Vec<LogMessage> {
+    log.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(parse_log_line)
+        .collect()
+}
+
+fn parse_log_line(line: &str) -> LogMessage {
+    let located = ["ERROR: ", "WARNING: "]
+        .iter()
+        .find_map(|severity| {
+            let (source_string, line, message) = parse_location(line.strip_prefix(severity)?)?;
+            let severity = severity.trim_end_matches([':', ' ']).to_lowercase();
+            Some((source_string, line, format!("{severity}: {message}")))
+        })
+        .or_else(|| {
+            parse_location(line)
+                .map(|(source_string, line, message)| (source_string, line, message.to_string()))
+        });
+
+    match located {
+        Some((source_string, line, message)) => LogMessage {
+            source_string: Some(source_string),
+            line: Some(line),
+            message,
+            file: None,
+            code: None,
+        },
+        None => LogMessage {
+            source_string: None,
+            line: None,
+            message: line.to_string(),
+            file: None,
+            code: None,
+        },
+    }
+}
+
+/// Parses `<source>:<line>[(<column>)]: <message>` or `<source>(<line>) : <message>`.
+fn parse_location(text: &str) -> Option<(u32, u32, &str)> {
+    let (source_string, rest) = split_number(text)?;
+
+    if let Some(rest) = rest.strip_prefix(':') {
+        let (line, mut rest) = split_number(rest)?;
+        if let Some(column) = rest.strip_prefix('(') {
+            rest = &column[column.find(')')? + 1..];
+        }
+        let message = rest.strip_prefix(':')?;
+        return Some((source_string, line, message.trim()));
+    }
+
+    let rest = rest.strip_prefix('(')?;
+    let (line, rest) = split_number(rest)?;
+    let message = rest.strip_prefix(')')?.trim_start().strip_prefix(':')?;
+    Some((source_string, line, message.trim()))
+}
+
+fn split_number(text: &str) -> Option<(u32, &str)> {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    Some((text[..end].parse().ok()?, &text[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_info_log_formats() {
+        let log = "0:12(5): error: `foo' undeclared\n\
+                   0(7) : error C1008: undefined variable \"bar\"\n\
+                   ERROR: 1:3: 'baz' : syntax error\n\
+                   some unlocated message\n";
+        let messages = parse_info_log(log);
+
+        let locations: Vec<_> = messages
+            .iter()
+            .map(|message| (message.source_string, message.line))
+            .collect();
+        assert_eq!(
+            locations,
+            vec![
+                (Some(0), Some(12)),
+                (Some(0), Some(7)),
+                (Some(1), Some(3)),
+                (None, None)
+            ]
+        );
+        assert_eq!(messages[0].message, "error: `foo' undeclared");
+        assert_eq!(
+            messages[1].message,
+            "error C1008: undefined variable \"bar\""
+        );
+        assert_eq!(messages[2].message, "error: 'baz' : syntax error");
+        assert_eq!(messages[3].message, "some unlocated message");
+    }
+
+    #[test]
+    fn test_locate_log_message() {
+        let mut message = parse_info_log("0:2(1): error: oops").remove(0);
+        message.locate(&[(
+            Path::new("shader.glsl"),
+            "#version 330 core\nvoid mian() {}\n",
+        )]);
+
+        assert_eq!(message.file, Some(PathBuf::from("shader.glsl")));
+        assert_eq!(message.code.as_deref(), Some("void mian() {}"));
+        assert_eq!(
+            message.to_string(),
+            "shader.glsl:2: error: oops\n     2 | void mian() {}\n"
+        );
+    }
+}
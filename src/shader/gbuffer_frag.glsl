@@ -0,0 +1,11 @@
+#version 430 core
+
+in vec3 ViewNormal;
+
+// Depth is written by the fixed function stages, the normal is all that's left for SSAO.
+out vec4 GNormal;
+
+void main()
+{
+    GNormal = vec4(normalize(ViewNormal), 1.0);
+}
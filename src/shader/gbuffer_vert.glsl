@@ -0,0 +1,17 @@
+#version 430 core
+#include "camera.glsl"
+
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+
+out vec3 ViewNormal;
+
+uniform mat4 model;
+
+void main()
+{
+    mat4 modelView = view * model;
+    ViewNormal = mat3(transpose(inverse(modelView))) * aNormal;
+
+    gl_Position = projection * modelView * vec4(aPos, 1.0);
+}
@@ -1,139 +1,63 @@
-#version 330 core
-out vec4 FragColor;
+#version 430 core
+#include "camera.glsl"
+#include "lighting.glsl"
+#include "shadow.glsl"
+#include "bloom.glsl"
+
+layout (location = 0) out vec4 FragColor;
+layout (location = 1) out vec4 BrightColor;
 
 struct Material {
     sampler2D diffuse;
     sampler2D specular;
-    float shininess;
-};
-
-struct SpotLight {
-    vec3 position;
-    vec3 direction;
-    float innerCutOff;
-    float outerCutOff;
-
-    vec3 ambient;
-    vec3 diffuse;
-    vec3 specular;
-
-    float constant;
-    float linear;
-    float quadratic;
-};
-uniform SpotLight spotLight;
-
-struct DirLight {
-    vec3 direction;
-
-    vec3 ambient;
-    vec3 diffuse;
-    vec3 specular;
+    // Baked occlusion of the ambient light, white for materials without one.
+    sampler2D ambientOcclusion;
 };
-uniform DirLight dirLight;
-
-struct PointLight {
-    vec3 position;
-
-    float constant;
-    float linear;
-    float quadratic;
 
-    vec3 ambient;
-    vec3 diffuse;
-    vec3 specular;
+// Set before drawing each mesh, see `MaterialBlock`.
+layout (std140, binding = 2) uniform MaterialBlock {
+    float shininess;
 };
-#define NR_POINT_LIGHTS 4
-uniform PointLight pointLights[NR_POINT_LIGHTS];
 
 in vec3 FragPos;
 in vec3 Normal;
 in vec2 TexCoords;
 
-uniform vec3 viewPos;
 uniform Material material;
-
-vec3 CalcDirLight(DirLight light, vec3 normal, vec3 viewDir);
-vec3 CalcPointLight(PointLight light, vec3 normal, vec3 fragPos, vec3 viewDir);
-vec3 CalcSpotLight(SpotLight spotLight, vec3 norm, vec3 fragPos, vec3 viewDir);
+// Occlusion of the ambient light by nearby geometry, covering the screen, see `Ssao`.
+uniform sampler2D ssao;
 
 void main() {
     vec3 viewDir = normalize(viewPos - FragPos);
     vec3 norm = normalize(Normal);
-
+    vec2 screenUv = gl_FragCoord.xy / vec2(textureSize(ssao, 0));
+    Surface surface = Surface(
+        texture(material.diffuse, TexCoords).rgb,
+        texture(material.specular, TexCoords).rgb,
+        shininess,
+        texture(material.ambientOcclusion, TexCoords).r * texture(ssao, screenUv).r
+    );
+
+    vec3 result = vec3(0.0);
     // Directional Lighting
-    vec3 result = CalcDirLight(dirLight, norm, viewDir);
+    for (uint i = 0; i < dirLightCount; i++) {
+        float shadow = 0.0;
+        if (int(i) == shadowLight) {
+            shadow = CalcDirShadow(FragPos, norm, normalize(-dirLights[i].direction));
+        }
+        result += CalcDirLight(dirLights[i], surface, norm, viewDir, shadow);
+    }
     // Spot Lighting
-    result += CalcSpotLight(spotLight, norm, FragPos, viewDir);
+    for (uint i = 0; i < spotLightCount; i++) {
+        float shadow = CalcSpotShadow(spotLights[i], FragPos, norm);
+        result += CalcSpotLight(spotLights[i], surface, norm, FragPos, viewDir, shadow);
+    }
     // Point Lighting
-    for (int i = 0; i < NR_POINT_LIGHTS; i++) {
-        result += CalcPointLight(pointLights[i], norm, FragPos, viewDir);
+    for (uint i = 0; i < pointLightCount; i++) {
+        float shadow = CalcPointShadow(pointLights[i], FragPos);
+        result += CalcPointLight(pointLights[i], surface, norm, FragPos, viewDir, shadow);
     }
+    result *= CascadeDebugTint(FragPos);
     FragColor = vec4(result, 1.0);
-}
-
-vec3 CalcDirLight(DirLight light, vec3 normal, vec3 viewDir) {
-    vec3 lightDir = normalize(-light.direction);
-    // diffuse shading
-    float diff = max(dot(normal, lightDir), 0.0);
-    // specular shading
-    vec3 reflectDir = reflect(-lightDir, normal);
-    float spec = pow(max(dot(viewDir, reflectDir), 0.0), material.shininess);
-    // combine results
-    vec3 ambient = light.ambient * vec3(texture(material.diffuse, TexCoords));
-    vec3 diffuse = light.diffuse * diff * vec3(texture(material.diffuse, TexCoords));
-    vec3 specular = light.specular * spec * vec3(texture(material.specular, TexCoords));
-    return (ambient + diffuse + specular);
-}
-
-vec3 CalcPointLight(PointLight light, vec3 normal, vec3 fragPos, vec3 viewDir) {
-    vec3 lightDir = normalize(light.position - fragPos);
-    // diffuse shading
-    float diff = max(dot(normal, lightDir), 0.0);
-    // specular shading
-    vec3 reflectDir = reflect(-lightDir, normal);
-    float spec = pow(max(dot(viewDir, reflectDir), 0.0), material.shininess);
-    // attenuation
-    float distance = length(light.position - fragPos);
-    float attenuation = 1.0 / (light.constant + light.linear * distance +
-                light.quadratic * (distance * distance));
-    // combine results
-    vec3 ambient = light.ambient * vec3(texture(material.diffuse, TexCoords));
-    vec3 diffuse = light.diffuse * diff * vec3(texture(material.diffuse, TexCoords));
-    vec3 specular = light.specular * spec * vec3(texture(material.specular, TexCoords));
-    ambient *= attenuation;
-    diffuse *= attenuation;
-    specular *= attenuation;
-    return (ambient + diffuse + specular);
-}
-
-vec3 CalcSpotLight(SpotLight light, vec3 normal, vec3 fragPos, vec3 viewDir) {
-    // ambient
-    vec3 ambient = light.ambient * texture(material.diffuse, TexCoords).rgb;
-
-    // diffuse
-    vec3 lightDir = normalize(light.position - fragPos);
-    float diff = max(dot(normal, lightDir), 0.0);
-    vec3 diffuse = light.diffuse * diff * texture(material.diffuse, TexCoords).rgb;
-
-    // specular
-    vec3 reflectDir = reflect(-lightDir, normal);
-    float spec = pow(max(dot(viewDir, reflectDir), 0.0), material.shininess);
-    vec3 specular = light.specular * spec * texture(material.specular, TexCoords).rgb;
-
-    // spotlight (soft edges)
-    float theta = dot(lightDir, normalize(-light.direction));
-    float epsilon = (light.innerCutOff - light.outerCutOff);
-    float intensity = clamp((theta - light.outerCutOff) / epsilon, 0.0, 1.0);
-    diffuse *= intensity;
-    specular *= intensity;
-
-    // attenuation
-    float distance = length(light.position - fragPos);
-    float attenuation = 1.0 / (light.constant + light.linear * distance + light.quadratic * (distance * distance));
-    ambient *= attenuation;
-    diffuse *= attenuation;
-    specular *= attenuation;
-
-    return (ambient + diffuse + specular);
+    BrightColor = BloomColor(result);
 }
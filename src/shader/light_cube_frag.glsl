@@ -1,7 +1,14 @@
-#version 330 core
-out vec4 FragColor;
+#version 430 core
+#include "bloom.glsl"
+
+layout (location = 0) out vec4 FragColor;
+layout (location = 1) out vec4 BrightColor;
+
+// Bright enough for the light to glow.
+uniform vec3 lightColor;
 
 void main()
 {
-    FragColor = vec4(1.0); // set all 4 vector values to 1.0
-}
\ No newline at end of file
+    FragColor = vec4(lightColor, 1.0);
+    BrightColor = BloomColor(lightColor);
+}
@@ -1,9 +1,9 @@
-#version 330 core
+#version 430 core
+#include "camera.glsl"
+
 layout(location = 0) in vec3 aPos;
 
 uniform mat4 model;
-uniform mat4 view;
-uniform mat4 projection;
 
 void main()
 {
@@ -0,0 +1,147 @@
+#pragma once
+
+// Light structs and the Phong lighting shared by every lit program.
+
+// Material colors sampled at the fragment being lit.
+struct Surface {
+    vec3 diffuse;
+    vec3 specular;
+    float shininess;
+    // How much of the ambient light reaches the fragment, 1 when none of it is blocked.
+    float occlusion;
+};
+
+// The light structs are ordered so std140 packs each float into the padding after a vec3, see
+// the Rust structs mirroring them.
+struct SpotLight {
+    vec3 position;
+    float innerCutOff;
+    vec3 direction;
+    float outerCutOff;
+
+    vec3 ambient;
+    float constant;
+    vec3 diffuse;
+    float linear;
+    vec3 specular;
+    float quadratic;
+
+    mat4 lightSpaceMatrix;
+    // Offset and size of the light's tile of spotShadowAtlas.
+    vec4 shadowRect;
+    float shadowBias;
+    uint hasShadow;
+};
+
+struct DirLight {
+    vec3 direction;
+
+    vec3 ambient;
+    vec3 diffuse;
+    vec3 specular;
+};
+
+struct PointLight {
+    vec3 position;
+    float constant;
+
+    vec3 ambient;
+    float linear;
+    vec3 diffuse;
+    float quadratic;
+    vec3 specular;
+    float farPlane;
+
+    float shadowBias;
+    // Cube of pointShadowMaps the light's shadow is in, -1 if it casts none.
+    int shadowLayer;
+};
+
+// The number of lights of each kind, see `LightsBlock`.
+layout (std140, binding = 1) uniform LightsBlock {
+    uint dirLightCount;
+    uint spotLightCount;
+    uint pointLightCount;
+};
+
+// Each kind of light grows with the number of lights, see `LightList`.
+layout (std430, binding = 0) readonly buffer PointLightsBlock {
+    PointLight pointLights[];
+};
+
+layout (std430, binding = 1) readonly buffer SpotLightsBlock {
+    SpotLight spotLights[];
+};
+
+layout (std430, binding = 2) readonly buffer DirLightsBlock {
+    DirLight dirLights[];
+};
+
+// `shadow` is how much of the light is blocked, see `CalcDirShadow`.
+vec3 CalcDirLight(DirLight light, Surface surface, vec3 normal, vec3 viewDir, float shadow) {
+    vec3 lightDir = normalize(-light.direction);
+    // diffuse shading
+    float diff = max(dot(normal, lightDir), 0.0);
+    // specular shading
+    vec3 reflectDir = reflect(-lightDir, normal);
+    float spec = pow(max(dot(viewDir, reflectDir), 0.0), surface.shininess);
+    // combine results
+    vec3 ambient = light.ambient * surface.diffuse * surface.occlusion;
+    vec3 diffuse = light.diffuse * diff * surface.diffuse;
+    vec3 specular = light.specular * spec * surface.specular;
+    return (ambient + (1.0 - shadow) * (diffuse + specular));
+}
+
+vec3 CalcPointLight(PointLight light, Surface surface, vec3 normal, vec3 fragPos, vec3 viewDir,
+                    float shadow) {
+    vec3 lightDir = normalize(light.position - fragPos);
+    // diffuse shading
+    float diff = max(dot(normal, lightDir), 0.0);
+    // specular shading
+    vec3 reflectDir = reflect(-lightDir, normal);
+    float spec = pow(max(dot(viewDir, reflectDir), 0.0), surface.shininess);
+    // attenuation
+    float distance = length(light.position - fragPos);
+    float attenuation = 1.0 / (light.constant + light.linear * distance +
+                light.quadratic * (distance * distance));
+    // combine results
+    vec3 ambient = light.ambient * surface.diffuse * surface.occlusion;
+    vec3 diffuse = light.diffuse * diff * surface.diffuse;
+    vec3 specular = light.specular * spec * surface.specular;
+    ambient *= attenuation;
+    diffuse *= attenuation;
+    specular *= attenuation;
+    return (ambient + (1.0 - shadow) * (diffuse + specular));
+}
+
+vec3 CalcSpotLight(SpotLight light, Surface surface, vec3 normal, vec3 fragPos, vec3 viewDir,
+                   float shadow) {
+    // ambient
+    vec3 ambient = light.ambient * surface.diffuse * surface.occlusion;
+
+    // diffuse
+    vec3 lightDir = normalize(light.position - fragPos);
+    float diff = max(dot(normal, lightDir), 0.0);
+    vec3 diffuse = light.diffuse * diff * surface.diffuse;
+
+    // specular
+    vec3 reflectDir = reflect(-lightDir, normal);
+    float spec = pow(max(dot(viewDir, reflectDir), 0.0), surface.shininess);
+    vec3 specular = light.specular * spec * surface.specular;
+
+    // spotlight (soft edges)
+    float theta = dot(lightDir, normalize(-light.direction));
+    float epsilon = (light.innerCutOff - light.outerCutOff);
+    float intensity = clamp((theta - light.outerCutOff) / epsilon, 0.0, 1.0);
+    diffuse *= intensity;
+    specular *= intensity;
+
+    // attenuation
+    float distance = length(light.position - fragPos);
+    float attenuation = 1.0 / (light.constant + light.linear * distance + light.quadratic * (distance * distance));
+    ambient *= attenuation;
+    diffuse *= attenuation;
+    specular *= attenuation;
+
+    return (ambient + (1.0 - shadow) * (diffuse + specular));
+}
@@ -0,0 +1,15 @@
+#version 430 core
+
+in vec2 TexCoords;
+
+out float LogLuminance;
+
+uniform sampler2D hdrTexture;
+
+// Averaging the log rather than the luminance itself keeps a few bright lights from
+// dominating the result.
+void main()
+{
+    vec3 color = texture(hdrTexture, TexCoords).rgb;
+    LogLuminance = log(max(dot(color, vec3(0.2126, 0.7152, 0.0722)), 0.0001));
+}
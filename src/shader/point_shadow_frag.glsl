@@ -0,0 +1,12 @@
+#version 430 core
+
+in vec3 FragPos;
+
+uniform vec3 lightPos;
+uniform float farPlane;
+
+void main()
+{
+    // Linear distance to the light, so lookups don't depend on which face they land on.
+    gl_FragDepth = length(FragPos - lightPos) / farPlane;
+}
@@ -0,0 +1,24 @@
+#version 430 core
+
+layout (triangles) in;
+layout (triangle_strip, max_vertices = 18) out;
+
+// View projection matrix of each face, in the +X, -X, +Y, -Y, +Z, -Z order of cube map layers.
+uniform mat4 faceMatrices[6];
+// The cube of the array being rendered into.
+uniform int shadowLayer;
+
+out vec3 FragPos;
+
+void main()
+{
+    for (int face = 0; face < 6; face++) {
+        gl_Layer = 6 * shadowLayer + face;
+        for (int i = 0; i < 3; i++) {
+            FragPos = gl_in[i].gl_Position.xyz;
+            gl_Position = faceMatrices[face] * gl_in[i].gl_Position;
+            EmitVertex();
+        }
+        EndPrimitive();
+    }
+}
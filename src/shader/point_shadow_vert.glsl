@@ -0,0 +1,11 @@
+#version 430 core
+
+layout (location = 0) in vec3 aPos;
+
+uniform mat4 model;
+
+void main()
+{
+    // Projected once per cube face by the geometry shader.
+    gl_Position = model * vec4(aPos, 1.0);
+}
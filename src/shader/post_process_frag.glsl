@@ -0,0 +1,61 @@
+#version 430 core
+
+in vec2 TexCoords;
+
+out vec4 FragColor;
+
+uniform sampler2D screenTexture;
+uniform sampler3D colorLut;
+// One of the EFFECT_* values defined from Rust.
+uniform int effect;
+// Weights of the 3x3 neighbourhood, indexed by horizontal then vertical offset.
+uniform mat3 kernel;
+// Radius, softness and strength.
+uniform vec3 vignette;
+uniform float gradeStrength;
+
+vec3 ApplyKernel()
+{
+    vec2 texelSize = 1.0 / vec2(textureSize(screenTexture, 0));
+    vec3 result = vec3(0.0);
+    for (int x = -1; x <= 1; x++) {
+        for (int y = -1; y <= 1; y++) {
+            vec3 color = texture(screenTexture, TexCoords + vec2(x, y) * texelSize).rgb;
+            result += kernel[x + 1][y + 1] * color;
+        }
+    }
+    return result;
+}
+
+vec3 ApplyColorGrade(vec3 color)
+{
+    // Sample the centers of the outermost texels for black and white.
+    float size = float(textureSize(colorLut, 0).x);
+    vec3 coords = clamp(color, 0.0, 1.0) * (size - 1.0) / size + 0.5 / size;
+    return mix(color, texture(colorLut, coords).rgb, gradeStrength);
+}
+
+void main()
+{
+    vec3 color = texture(screenTexture, TexCoords).rgb;
+    switch (effect) {
+        case EFFECT_GRAYSCALE:
+            color = vec3(dot(color, vec3(0.2126, 0.7152, 0.0722)));
+            break;
+        case EFFECT_INVERT:
+            color = 1.0 - color;
+            break;
+        case EFFECT_KERNEL:
+            color = ApplyKernel();
+            break;
+        case EFFECT_VIGNETTE: {
+            float dist = distance(TexCoords, vec2(0.5));
+            color *= 1.0 - vignette.z * smoothstep(vignette.x, vignette.x + vignette.y, dist);
+            break;
+        }
+        case EFFECT_COLOR_GRADE:
+            color = ApplyColorGrade(color);
+            break;
+    }
+    FragColor = vec4(color, 1.0);
+}
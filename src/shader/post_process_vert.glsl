@@ -0,0 +1,10 @@
+#version 430 core
+
+out vec2 TexCoords;
+
+// A triangle covering the whole screen, made up from the vertex index so no buffers are needed.
+void main()
+{
+    TexCoords = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2);
+    gl_Position = vec4(TexCoords * 2.0 - 1.0, 0.0, 1.0);
+}
@@ -0,0 +1,318 @@
+use std::{
+    fmt, fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use super::{LogMessage, ShaderError};
+
+/// Expands `#include "file"` directives and injects `#define`s from Rust before a shader is
+/// compiled. Included paths are relative to the including file, and `#pragma once` skips a
+/// file that was already included.
+/// ```
+/// # use learn_ogl_rs::shader::Preprocessor;
+/// let preprocessor = Preprocessor::new()
+///     .define("NR_POINT_LIGHTS", 4)
+///     .flag("USE_SHADOWS");
+/// assert_eq!(preprocessor.defines(), "#define NR_POINT_LIGHTS 4\n#define USE_SHADOWS\n");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Preprocessor {
+    defines: Vec<(String, String)>,
+}
+
+/// Expanded shader source. Every file gets its own source string number in the emitted `#line`
+/// directives, which is its index into `files`.
+#[derive(Debug, Clone)]
+pub struct Source {
+    pub code: String,
+    /// Path and contents of every file the source was built from, starting with the root file.
+    pub files: Vec<(PathBuf, String)>,
+}
+
+impl Source {
+    /// Resolves the file and offending line of a message from the driver's info log.
+    pub fn locate(&self, message: &mut LogMessage) {
+        let files: Vec<_> = self
+            .files
+            .iter()
+            .map(|(path, contents)| (path.as_path(), contents.as_str()))
+            .collect();
+        message.locate(&files);
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|(path, _)| path.as_path())
+    }
+}
+
+impl Preprocessor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `#define name value`, replacing an earlier define of the same name.
+    pub fn define(mut self, name: &str, value: impl fmt::Display) -> Self {
+        let value = value.to_string();
+        match self.defines.iter_mut().find(|(defined, _)| defined == name) {
+            Some((_, old_value)) => *old_value = value,
+            None => self.defines.push((name.to_string(), value)),
+        }
+        self
+    }
+
+    /// Adds a valueless `#define name`, for features toggled with `#ifdef`.
+    pub fn flag(self, name: &str) -> Self {
+        self.define(name, "")
+    }
+
+    /// The defines as GLSL, inserted right after the `#version` directive.
+    pub fn defines(&self) -> String {
+        self.defines
+            .iter()
+            .map(|(name, value)| {
+                if value.is_empty() {
+                    format!("#define {name}\n")
+                } else {
+                    format!("#define {name} {value}\n")
+                }
+            })
+            .collect()
+    }
+
+    pub fn process(&self, path: &Path) -> Result<Source, ShaderError> {
+        self.process_with(path, |path| fs::read_to_string(path))
+    }
+
+    /// Like `process`, reading files with `read` instead of from disk.
+    pub fn process_with(
+        &self,
+        path: &Path,
+        read: impl FnMut(&Path) -> io::Result<String>,
+    ) -> Result<Source, ShaderError> {
+        let mut expander = Expander {
+            defines: self.defines(),
+            read,
+            source: Source {
+                code: String::new(),
+                files: vec![],
+            },
+            stack: vec![],
+            once: vec![],
+            has_version: false,
+        };
+        expander.expand(path)?;
+
+        if !expander.has_version {
+            return Err(ShaderError::Preprocess {
+                path: path.into(),
+                line: 1,
+                message: String::from("missing #version directive"),
+            });
+        }
+        Ok(expander.source)
+    }
+}
+
+struct Expander<R> {
+    defines: String,
+    read: R,
+    source: Source,
+    /// Files currently being expanded, to detect include cycles.
+    stack: Vec<PathBuf>,
+    /// Files that contained `#pragma once`.
+    once: Vec<PathBuf>,
+    has_version: bool,
+}
+
+impl<R: FnMut(&Path) -> io::Result<String>> Expander<R> {
+    fn expand(&mut self, path: &Path) -> Result<(), ShaderError> {
+        let path = normalize(path);
+        if self.once.contains(&path) {
+            return Ok(());
+        }
+        if let Some(start) = self.stack.iter().position(|included| *included == path) {
+            let mut chain = self.stack[start..].to_vec();
+            chain.push(path);
+            return Err(ShaderError::IncludeCycle(chain));
+        }
+
+        let index = self.file_index(&path)?;
+        let contents = self.source.files[index].1.clone();
+        let is_root = self.stack.is_empty();
+        if !is_root {
+            self.line_directive(1, index);
+        }
+        self.stack.push(path.clone());
+
+        for (line_index, line) in contents.lines().enumerate() {
+            let line_number = line_index as u32 + 1;
+            let error = |message: &str| ShaderError::Preprocess {
+                path: path.clone(),
+                line: line_number,
+                message: message.to_string(),
+            };
+
+            let Some(directive) = line.trim_start().strip_prefix('#') else {
+                self.push_line(line);
+                continue;
+            };
+            let directive = directive.trim_start();
+            let (name, args) = directive
+                .split_once(char::is_whitespace)
+                .unwrap_or((directive, ""));
+
+            match (name, args.trim()) {
+                ("version", _) => {
+                    if !is_root || self.has_version {
+                        return Err(error("#version is only allowed once, in the root file"));
+                    }
+                    self.has_version = true;
+                    self.push_line(line);
+                    self.source.code.push_str(&self.defines);
+                    self.line_directive(line_number + 1, index);
+                }
+                ("include", args) => {
+                    let Some(file) = args
+                        .strip_prefix('"')
+                        .and_then(|args| args.strip_suffix('"'))
+                    else {
+                        return Err(error("expected #include \"file\""));
+                    };
+                    let include = path.parent().unwrap_or(Path::new("")).join(file);
+                    self.expand(&include)?;
+                    self.line_directive(line_number + 1, index);
+                }
+                ("pragma", "once") => {
+                    self.once.push(path.clone());
+                    self.push_line("");
+                }
+                _ => self.push_line(line),
+            }
+        }
+
+        self.stack.pop();
+        Ok(())
+    }
+
+    fn file_index(&mut self, path: &Path) -> Result<usize, ShaderError> {
+        if let Some(index) = self.source.paths().position(|file| file == path) {
+            return Ok(index);
+        }
+        let contents = (self.read)(path).map_err(|err| ShaderError::Io {
+            path: path.into(),
+            err,
+        })?;
+        self.source.files.push((path.into(), contents));
+        Ok(self.source.files.len() - 1)
+    }
+
+    fn push_line(&mut self, line: &str) {
+        self.source.code.push_str(line);
+        self.source.code.push('\n');
+    }
+
+    fn line_directive(&mut self, line: u32, source_string: usize) {
+        self.push_line(&format!("#line {line} {source_string}"));
+    }
+}
+
+/// Removes `.` and `..` components without touching the file system, so the same file
+/// included through different relative paths is recognized.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn process(preprocessor: &Preprocessor, files: &[(&str, &str)]) -> Result<Source, ShaderError> {
+        let files: HashMap<PathBuf, String> = files
+            .iter()
+            .map(|(path, contents)| (PathBuf::from(path), contents.to_string()))
+            .collect();
+        preprocessor.process_with(Path::new("shaders/main.glsl"), |path| {
+            files
+                .get(path)
+                .cloned()
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+        })
+    }
+
+    #[test]
+    fn test_expand_includes_and_defines() {
+        let source = process(
+            &Preprocessor::new().define("NR_POINT_LIGHTS", 4),
+            &[
+                (
+                    "shaders/main.glsl",
+                    "#version 330 core\n#include \"common/light.glsl\"\nvoid main() {}\n",
+                ),
+                (
+                    "shaders/common/light.glsl",
+                    "#pragma once\n#include \"../common/light.glsl\"\nstruct Light {};\n",
+                ),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            source.code,
+            "#version 330 core\n\
+             #define NR_POINT_LIGHTS 4\n\
+             #line 2 0\n\
+             #line 1 1\n\
+             \n\
+             #line 3 1\n\
+             struct Light {};\n\
+             #line 3 0\n\
+             void main() {}\n"
+        );
+        let paths: Vec<_> = source.paths().collect();
+        assert_eq!(
+            paths,
+            [
+                Path::new("shaders/main.glsl"),
+                Path::new("shaders/common/light.glsl")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_detect_include_cycle() {
+        let err = process(
+            &Preprocessor::new(),
+            &[
+                (
+                    "shaders/main.glsl",
+                    "#version 330 core\n#include \"a.glsl\"\n",
+                ),
+                ("shaders/a.glsl", "#include \"b.glsl\"\n"),
+                ("shaders/b.glsl", "#include \"a.glsl\"\n"),
+            ],
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Include cycle: shaders/a.glsl -> shaders/b.glsl -> shaders/a.glsl"
+        );
+    }
+}
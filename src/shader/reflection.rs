@@ -0,0 +1,500 @@
+use std::{collections::HashMap, ffi::CString};
+
+use crate::gl::{
+    self,
+    types::{GLchar, GLenum, GLint, GLsizei, GLuint},
+    Gl,
+};
+
+/// What a linked program expects: its active uniforms, uniform blocks and vertex attributes,
+/// queried once at link time.
+#[derive(Debug, Clone, Default)]
+pub struct Reflection {
+    uniforms: Vec<ActiveUniform>,
+    /// Location and index into `uniforms` of every uniform outside a block. Arrays of basic
+    /// types can be looked up by their name, and by the name of each element.
+    locations: HashMap<String, (GLint, usize)>,
+    blocks: Vec<UniformBlock>,
+    storage_blocks: Vec<StorageBlock>,
+    attributes: Vec<ActiveAttribute>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ActiveUniform {
+    /// Name as reported by the driver, arrays of basic types end in `[0]`.
+    pub name: String,
+    pub gl_type: GLenum,
+    /// Number of array elements, 1 if the uniform isn't an array.
+    pub size: GLint,
+    /// -1 for uniforms in a block.
+    pub location: GLint,
+    pub block_index: Option<GLuint>,
+    /// Byte offset from the start of its block.
+    pub offset: Option<GLint>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UniformBlock {
+    pub name: String,
+    pub index: GLuint,
+    pub binding: GLuint,
+    pub data_size: GLint,
+}
+
+#[derive(Debug, Clone)]
+pub struct StorageBlock {
+    pub name: String,
+    pub index: GLuint,
+    pub binding: GLuint,
+    /// Stride of the block's runtime-sized array, if it ends in one.
+    pub array_stride: Option<GLint>,
+    /// Name and byte offset of each member, those of a runtime-sized array only for its first
+    /// element, like `lights[0].position`.
+    pub members: Vec<(String, GLint)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ActiveAttribute {
+    pub name: String,
+    pub gl_type: GLenum,
+    pub size: GLint,
+    pub location: GLint,
+}
+
+impl ActiveAttribute {
+    /// Integer attributes are read as `int`/`uint` in the shader instead of being converted
+    /// to floats.
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self.gl_type,
+            gl::INT
+                | gl::INT_VEC2
+                | gl::INT_VEC3
+                | gl::INT_VEC4
+                | gl::UNSIGNED_INT
+                | gl::UNSIGNED_INT_VEC2
+                | gl::UNSIGNED_INT_VEC3
+                | gl::UNSIGNED_INT_VEC4
+        )
+    }
+}
+
+impl Reflection {
+    pub fn new(gl: &Gl, program: GLuint) -> Self {
+        let uniforms = active_uniforms(gl, program);
+
+        let mut locations = HashMap::new();
+        for (index, uniform) in uniforms.iter().enumerate() {
+            if uniform.block_index.is_some() {
+                continue;
+            }
+            locations.insert(uniform.name.clone(), (uniform.location, index));
+
+            let Some(base) = uniform.name.strip_suffix("[0]") else {
+                continue;
+            };
+            locations.insert(base.to_string(), (uniform.location, index));
+            for element in 1..uniform.size {
+                let name = format!("{base}[{element}]");
+                let location = uniform_location(gl, program, &name);
+                locations.insert(name, (location, index));
+            }
+        }
+
+        Self {
+            uniforms,
+            locations,
+            blocks: active_uniform_blocks(gl, program),
+            storage_blocks: active_storage_blocks(gl, program),
+            attributes: active_attributes(gl, program),
+        }
+    }
+
+    /// Finds a uniform outside a block along with its location, `name` can also be an element
+    /// of an array.
+    pub fn uniform(&self, name: &str) -> Option<(GLint, &ActiveUniform)> {
+        self.locations
+            .get(name)
+            .map(|(location, index)| (*location, &self.uniforms[*index]))
+    }
+
+    pub fn uniforms(&self) -> &[ActiveUniform] {
+        &self.uniforms
+    }
+    pub fn blocks(&self) -> &[UniformBlock] {
+        &self.blocks
+    }
+    pub fn block(&self, name: &str) -> Option<&UniformBlock> {
+        self.blocks.iter().find(|block| block.name == name)
+    }
+    pub fn storage_blocks(&self) -> &[StorageBlock] {
+        &self.storage_blocks
+    }
+    pub fn storage_block(&self, name: &str) -> Option<&StorageBlock> {
+        self.storage_blocks.iter().find(|block| block.name == name)
+    }
+    pub fn attributes(&self) -> &[ActiveAttribute] {
+        &self.attributes
+    }
+}
+
+/// Whether a uniform of type `found` can be set with the `glUniform*` call used for `expected`.
+/// Samplers and bools are set as ints.
+pub fn accepts(expected: GLenum, found: GLenum) -> bool {
+    expected == found || (expected == gl::INT && (found == gl::BOOL || is_sampler(found)))
+}
+
+/// Every sampler type uniforms can be set to a texture unit for, with its GLSL name.
+const SAMPLER_TYPES: [(GLenum, &str); 12] = [
+    (gl::SAMPLER_2D, "sampler2D"),
+    (gl::SAMPLER_3D, "sampler3D"),
+    (gl::SAMPLER_CUBE, "samplerCube"),
+    (gl::SAMPLER_2D_SHADOW, "sampler2DShadow"),
+    (gl::SAMPLER_2D_ARRAY, "sampler2DArray"),
+    (gl::SAMPLER_2D_ARRAY_SHADOW, "sampler2DArrayShadow"),
+    (gl::SAMPLER_CUBE_SHADOW, "samplerCubeShadow"),
+    (gl::SAMPLER_CUBE_MAP_ARRAY, "samplerCubeArray"),
+    (gl::SAMPLER_CUBE_MAP_ARRAY_SHADOW, "samplerCubeArrayShadow"),
+    (gl::SAMPLER_2D_MULTISAMPLE, "sampler2DMS"),
+    (gl::INT_SAMPLER_2D, "isampler2D"),
+    (gl::UNSIGNED_INT_SAMPLER_2D, "usampler2D"),
+];
+
+pub fn is_sampler(gl_type: GLenum) -> bool {
+    SAMPLER_TYPES
+        .iter()
+        .any(|(sampler_type, _)| *sampler_type == gl_type)
+}
+
+/// The GLSL name of a uniform or attribute type, for error messages.
+pub fn glsl_type_name(gl_type: GLenum) -> &'static str {
+    match gl_type {
+        gl::FLOAT => "float",
+        gl::FLOAT_VEC2 => "vec2",
+        gl::FLOAT_VEC3 => "vec3",
+        gl::FLOAT_VEC4 => "vec4",
+        gl::INT => "int",
+        gl::INT_VEC2 => "ivec2",
+        gl::INT_VEC3 => "ivec3",
+        gl::INT_VEC4 => "ivec4",
+        gl::UNSIGNED_INT => "uint",
+        gl::UNSIGNED_INT_VEC2 => "uvec2",
+        gl::UNSIGNED_INT_VEC3 => "uvec3",
+        gl::UNSIGNED_INT_VEC4 => "uvec4",
+        gl::BOOL => "bool",
+        gl::BOOL_VEC2 => "bvec2",
+        gl::BOOL_VEC3 => "bvec3",
+        gl::BOOL_VEC4 => "bvec4",
+        gl::FLOAT_MAT2 => "mat2",
+        gl::FLOAT_MAT3 => "mat3",
+        gl::FLOAT_MAT4 => "mat4",
+        gl::FLOAT_MAT2x3 => "mat2x3",
+        gl::FLOAT_MAT2x4 => "mat2x4",
+        gl::FLOAT_MAT3x2 => "mat3x2",
+        gl::FLOAT_MAT3x4 => "mat3x4",
+        gl::FLOAT_MAT4x2 => "mat4x2",
+        gl::FLOAT_MAT4x3 => "mat4x3",
+        _ => SAMPLER_TYPES
+            .iter()
+            .find(|(sampler_type, _)| *sampler_type == gl_type)
+            .map_or("unknown type", |(_, name)| name),
+    }
+}
+
+fn uniform_location(gl: &Gl, program: GLuint, name: &str) -> GLint {
+    let name = CString::new(name).unwrap();
+    unsafe { gl.GetUniformLocation(program, name.as_ptr()) }
+}
+
+/// Reads a name through one of the `glGetActive*Name` style calls.
+fn read_name(max_len: GLint, read: impl FnOnce(GLsizei, *mut GLsizei, *mut GLchar)) -> String {
+    let mut name = vec![0_u8; max_len.max(1) as usize];
+    let mut name_len: GLsizei = 0;
+    read(
+        name.len() as GLsizei,
+        &mut name_len,
+        name.as_mut_ptr().cast(),
+    );
+    name.truncate(name_len as usize);
+    String::from_utf8_lossy(&name).into_owned()
+}
+
+fn active_uniforms(gl: &Gl, program: GLuint) -> Vec<ActiveUniform> {
+    let mut count: GLint = 0;
+    let mut max_name_len: GLint = 0;
+    unsafe {
+        gl.GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
+        gl.GetProgramiv(program, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_name_len);
+    }
+
+    (0..count as GLuint)
+        .map(|index| {
+            let mut size: GLint = 0;
+            let mut gl_type: GLenum = 0;
+            let name = read_name(max_name_len, |len, name_len, name| unsafe {
+                gl.GetActiveUniform(program, index, len, name_len, &mut size, &mut gl_type, name)
+            });
+
+            let mut block_index: GLint = -1;
+            let mut offset: GLint = -1;
+            unsafe {
+                gl.GetActiveUniformsiv(
+                    program,
+                    1,
+                    &index,
+                    gl::UNIFORM_BLOCK_INDEX,
+                    &mut block_index,
+                );
+                gl.GetActiveUniformsiv(program, 1, &index, gl::UNIFORM_OFFSET, &mut offset);
+            }
+            let in_block = block_index != -1;
+
+            ActiveUniform {
+                location: if in_block {
+                    -1
+                } else {
+                    uniform_location(gl, program, &name)
+                },
+                name,
+                gl_type,
+                size,
+                block_index: in_block.then_some(block_index as GLuint),
+                offset: in_block.then_some(offset),
+            }
+        })
+        .collect()
+}
+
+fn active_uniform_blocks(gl: &Gl, program: GLuint) -> Vec<UniformBlock> {
+    let mut count: GLint = 0;
+    let mut max_name_len: GLint = 0;
+    unsafe {
+        gl.GetProgramiv(program, gl::ACTIVE_UNIFORM_BLOCKS, &mut count);
+        gl.GetProgramiv(
+            program,
+            gl::ACTIVE_UNIFORM_BLOCK_MAX_NAME_LENGTH,
+            &mut max_name_len,
+        );
+    }
+
+    (0..count as GLuint)
+        .map(|index| {
+            let name = read_name(max_name_len, |len, name_len, name| unsafe {
+                gl.GetActiveUniformBlockName(program, index, len, name_len, name)
+            });
+            let mut binding: GLint = 0;
+            let mut data_size: GLint = 0;
+            unsafe {
+                gl.GetActiveUniformBlockiv(program, index, gl::UNIFORM_BLOCK_BINDING, &mut binding);
+                gl.GetActiveUniformBlockiv(
+                    program,
+                    index,
+                    gl::UNIFORM_BLOCK_DATA_SIZE,
+                    &mut data_size,
+                );
+            }
+            UniformBlock {
+                name,
+                index,
+                binding: binding as GLuint,
+                data_size,
+            }
+        })
+        .collect()
+}
+
+fn active_storage_blocks(gl: &Gl, program: GLuint) -> Vec<StorageBlock> {
+    let mut count: GLint = 0;
+    let mut max_name_len: GLint = 0;
+    let mut max_variable_name_len: GLint = 0;
+    unsafe {
+        gl.GetProgramInterfaceiv(
+            program,
+            gl::SHADER_STORAGE_BLOCK,
+            gl::ACTIVE_RESOURCES,
+            &mut count,
+        );
+        gl.GetProgramInterfaceiv(
+            program,
+            gl::SHADER_STORAGE_BLOCK,
+            gl::MAX_NAME_LENGTH,
+            &mut max_name_len,
+        );
+        gl.GetProgramInterfaceiv(
+            program,
+            gl::BUFFER_VARIABLE,
+            gl::MAX_NAME_LENGTH,
+            &mut max_variable_name_len,
+        );
+    }
+
+    (0..count as GLuint)
+        .map(|index| {
+            let name = read_name(max_name_len, |len, name_len, name| unsafe {
+                gl.GetProgramResourceName(
+                    program,
+                    gl::SHADER_STORAGE_BLOCK,
+                    index,
+                    len,
+                    name_len,
+                    name,
+                )
+            });
+            let [binding, variable_count] = resource_properties(
+                gl,
+                program,
+                gl::SHADER_STORAGE_BLOCK,
+                index,
+                [gl::BUFFER_BINDING, gl::NUM_ACTIVE_VARIABLES],
+            );
+
+            let mut variables = vec![0; variable_count.max(0) as usize];
+            unsafe {
+                gl.GetProgramResourceiv(
+                    program,
+                    gl::SHADER_STORAGE_BLOCK,
+                    index,
+                    1,
+                    &gl::ACTIVE_VARIABLES,
+                    variables.len() as GLsizei,
+                    std::ptr::null_mut(),
+                    variables.as_mut_ptr(),
+                );
+            }
+            let mut array_stride = None;
+            let members = variables
+                .iter()
+                .map(|variable| {
+                    let variable = *variable as GLuint;
+                    let [offset, stride] = resource_properties(
+                        gl,
+                        program,
+                        gl::BUFFER_VARIABLE,
+                        variable,
+                        [gl::OFFSET, gl::TOP_LEVEL_ARRAY_STRIDE],
+                    );
+                    // Only the runtime-sized array, which has to be the last member, has a
+                    // stride.
+                    if stride != 0 {
+                        array_stride = Some(stride);
+                    }
+                    let name = read_name(max_variable_name_len, |len, name_len, name| unsafe {
+                        gl.GetProgramResourceName(
+                            program,
+                            gl::BUFFER_VARIABLE,
+                            variable,
+                            len,
+                            name_len,
+                            name,
+                        )
+                    });
+                    (name, offset)
+                })
+                .collect();
+
+            StorageBlock {
+                name,
+                index,
+                binding: binding as GLuint,
+                array_stride,
+                members,
+            }
+        })
+        .collect()
+}
+
+fn resource_properties<const N: usize>(
+    gl: &Gl,
+    program: GLuint,
+    interface: GLenum,
+    index: GLuint,
+    properties: [GLenum; N],
+) -> [GLint; N] {
+    let mut values = [0; N];
+    unsafe {
+        gl.GetProgramResourceiv(
+            program,
+            interface,
+            index,
+            N as GLsizei,
+            properties.as_ptr(),
+            N as GLsizei,
+            std::ptr::null_mut(),
+            values.as_mut_ptr(),
+        );
+    }
+    values
+}
+
+/// The vertex attributes `program` reads, leaving out built-ins like `gl_VertexID`.
+fn active_attributes(gl: &Gl, program: GLuint) -> Vec<ActiveAttribute> {
+    let mut count: GLint = 0;
+    let mut max_name_len: GLint = 0;
+    unsafe {
+        gl.GetProgramiv(program, gl::ACTIVE_ATTRIBUTES, &mut count);
+        gl.GetProgramiv(program, gl::ACTIVE_ATTRIBUTE_MAX_LENGTH, &mut max_name_len);
+    }
+
+    (0..count as GLuint)
+        .filter_map(|index| {
+            let mut size: GLint = 0;
+            let mut gl_type: GLenum = 0;
+            let name = read_name(max_name_len, |len, name_len, name| unsafe {
+                gl.GetActiveAttrib(program, index, len, name_len, &mut size, &mut gl_type, name)
+            });
+
+            // Built-ins are active but have no location.
+            let c_name = CString::new(name.as_str()).ok()?;
+            let location = unsafe { gl.GetAttribLocation(program, c_name.as_ptr()) };
+            (location != -1).then_some(ActiveAttribute {
+                name,
+                gl_type,
+                size,
+                location,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::shader::{TextureUnit, UniformValue};
+
+    use super::*;
+
+    #[test]
+    fn test_shader_samplers_accepted() {
+        let mut used = vec![];
+        for entry in fs::read_dir("src/shader").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|extension| extension != "glsl") {
+                continue;
+            }
+            let source = fs::read_to_string(&path).unwrap();
+            used.extend(
+                source
+                    .split(|c: char| !c.is_ascii_alphanumeric())
+                    .filter(|word| {
+                        let word = word.trim_start_matches(['i', 'u']);
+                        word.strip_prefix("sampler").is_some_and(|rest| {
+                            rest.starts_with(|c: char| c.is_ascii_uppercase() || c.is_ascii_digit())
+                        })
+                    })
+                    .map(str::to_string),
+            );
+        }
+        assert!(!used.is_empty());
+
+        for name in used {
+            let gl_type = SAMPLER_TYPES
+                .iter()
+                .find(|(_, sampler_name)| *sampler_name == name)
+                .map(|(gl_type, _)| *gl_type)
+                .unwrap_or_else(|| panic!("{name} isn't a known sampler type"));
+            assert!(TextureUnit::accepts(gl_type), "{name} isn't accepted");
+            assert_eq!(glsl_type_name(gl_type), name);
+        }
+    }
+}
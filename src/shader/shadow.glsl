@@ -0,0 +1,138 @@
+#pragma once
+#include "camera.glsl"
+#include "lighting.glsl"
+
+#define MAX_CASCADES 4
+
+// The cascaded shadow maps of one of the directional lights, see `ShadowBlock`.
+layout (std140, binding = 3) uniform ShadowBlock {
+    mat4 lightSpaceMatrices[MAX_CASCADES];
+    // Distance from the camera at which each cascade ends.
+    vec4 cascadeSplits;
+    // Index into dirLights of the light casting the shadow, -1 if none does.
+    int shadowLight;
+    float shadowBias;
+    int pcfRadius;
+    int cascadeCount;
+    uint debugCascades;
+};
+
+// One layer per cascade.
+uniform sampler2DArrayShadow shadowMap;
+// Every spot light's shadow map, each in a tile of its own, see `SpotLight.shadowRect`.
+uniform sampler2DShadow spotShadowAtlas;
+// One cube per point light casting shadows, see `PointLight.shadowLayer`.
+uniform samplerCubeArrayShadow pointShadowMaps;
+
+// Directions sampled around a fragment for the PCF of point shadows.
+const vec3 pointShadowOffsets[20] = vec3[](
+    vec3(1, 1, 1), vec3(1, -1, 1), vec3(-1, -1, 1), vec3(-1, 1, 1),
+    vec3(1, 1, -1), vec3(1, -1, -1), vec3(-1, -1, -1), vec3(-1, 1, -1),
+    vec3(1, 1, 0), vec3(1, -1, 0), vec3(-1, -1, 0), vec3(-1, 1, 0),
+    vec3(1, 0, 1), vec3(-1, 0, 1), vec3(1, 0, -1), vec3(-1, 0, -1),
+    vec3(0, 1, 1), vec3(0, -1, 1), vec3(0, -1, -1), vec3(0, 1, -1)
+);
+
+// The cascade whose shadow map covers `fragPos`, -1 if it's further away than all of them.
+int CascadeIndex(vec3 fragPos) {
+    float depth = -(view * vec4(fragPos, 1.0)).z;
+    for (int cascade = 0; cascade < cascadeCount; cascade++) {
+        if (depth < cascadeSplits[cascade]) {
+            return cascade;
+        }
+    }
+    return -1;
+}
+
+// How much of the light coming from `lightDir` is blocked before reaching `fragPos`, from 0
+// (fully lit) to 1, averaged over the shadow map texels within `pcfRadius`.
+float CalcDirShadow(vec3 fragPos, vec3 normal, vec3 lightDir) {
+    int cascade = CascadeIndex(fragPos);
+    if (cascade < 0) {
+        return 0.0;
+    }
+    vec4 lightSpacePos = lightSpaceMatrices[cascade] * vec4(fragPos, 1.0);
+    vec3 projCoords = lightSpacePos.xyz / lightSpacePos.w * 0.5 + 0.5;
+    // Past the far plane of the light's frustum, so nothing is known to block it.
+    if (projCoords.z > 1.0) {
+        return 0.0;
+    }
+
+    // Surfaces at grazing angles to the light need a larger bias to avoid shadow acne.
+    float bias = max(shadowBias * 10.0 * (1.0 - dot(normal, lightDir)), shadowBias);
+    vec2 texelSize = 1.0 / vec2(textureSize(shadowMap, 0).xy);
+    float lit = 0.0;
+    for (int x = -pcfRadius; x <= pcfRadius; x++) {
+        for (int y = -pcfRadius; y <= pcfRadius; y++) {
+            vec2 offset = vec2(x, y) * texelSize;
+            lit += texture(shadowMap, vec4(projCoords.xy + offset, cascade, projCoords.z - bias));
+        }
+    }
+    float samples = float((2 * pcfRadius + 1) * (2 * pcfRadius + 1));
+    return 1.0 - lit / samples;
+}
+
+// Color to multiply a fragment's by to show which cascade it's in, when `debugCascades` is set.
+vec3 CascadeDebugTint(vec3 fragPos) {
+    const vec3 tints[MAX_CASCADES] = vec3[](
+        vec3(1.0, 0.4, 0.4),
+        vec3(0.4, 1.0, 0.4),
+        vec3(0.4, 0.4, 1.0),
+        vec3(1.0, 1.0, 0.4)
+    );
+    int cascade = CascadeIndex(fragPos);
+    if (debugCascades == 0 || cascade < 0) {
+        return vec3(1.0);
+    }
+    return tints[cascade];
+}
+
+// Like `CalcDirShadow`, for a point light. The PCF radius grows with the distance to the viewer
+// since distant shadows cover fewer pixels.
+float CalcPointShadow(PointLight light, vec3 fragPos) {
+    if (light.shadowLayer < 0) {
+        return 0.0;
+    }
+    vec3 fragToLight = fragPos - light.position;
+    float depth = (length(fragToLight) - light.shadowBias) / light.farPlane;
+    if (depth > 1.0) {
+        return 0.0;
+    }
+
+    float diskRadius = (1.0 + length(viewPos - fragPos) / light.farPlane) / 25.0;
+    float lit = 0.0;
+    for (int i = 0; i < 20; i++) {
+        vec3 dir = fragToLight + pointShadowOffsets[i] * diskRadius;
+        lit += texture(pointShadowMaps, vec4(dir, light.shadowLayer), depth);
+    }
+    return 1.0 - lit / 20.0;
+}
+
+// Like `CalcDirShadow`, for a spot light. Samples are kept inside the light's tile so they
+// don't read the shadow maps of its neighbors.
+float CalcSpotShadow(SpotLight light, vec3 fragPos, vec3 normal) {
+    if (light.hasShadow == 0) {
+        return 0.0;
+    }
+    vec4 lightSpacePos = light.lightSpaceMatrix * vec4(fragPos, 1.0);
+    vec3 projCoords = lightSpacePos.xyz / lightSpacePos.w * 0.5 + 0.5;
+    if (lightSpacePos.w <= 0.0 || projCoords.z > 1.0) {
+        return 0.0;
+    }
+
+    vec3 lightDir = normalize(light.position - fragPos);
+    float bias = max(light.shadowBias * 10.0 * (1.0 - dot(normal, lightDir)), light.shadowBias);
+    vec2 texelSize = 1.0 / vec2(textureSize(spotShadowAtlas, 0));
+    vec2 tileMin = light.shadowRect.xy + 0.5 * texelSize;
+    vec2 tileMax = light.shadowRect.xy + light.shadowRect.zw - 0.5 * texelSize;
+    vec2 uv = light.shadowRect.xy + projCoords.xy * light.shadowRect.zw;
+    float lit = 0.0;
+    for (int x = -1; x <= 1; x++) {
+        for (int y = -1; y <= 1; y++) {
+            vec2 offset = vec2(x, y) * texelSize;
+            vec2 sampleUv = clamp(uv + offset, tileMin, tileMax);
+            lit += texture(spotShadowAtlas, vec3(sampleUv, projCoords.z - bias));
+        }
+    }
+    return 1.0 - lit / 9.0;
+}
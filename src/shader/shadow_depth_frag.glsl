@@ -0,0 +1,6 @@
+#version 430 core
+
+// Only depth is written, which the fixed function stages take care of.
+void main()
+{
+}
@@ -0,0 +1,20 @@
+#version 430 core
+
+in vec2 TexCoords;
+
+out float Occlusion;
+
+uniform sampler2D source;
+
+// Averages a block the size of the noise texture, which removes the noise's pattern.
+void main()
+{
+    vec2 texelSize = 1.0 / vec2(textureSize(source, 0));
+    float result = 0.0;
+    for (int x = -NOISE_SIZE / 2; x < NOISE_SIZE / 2; x++) {
+        for (int y = -NOISE_SIZE / 2; y < NOISE_SIZE / 2; y++) {
+            result += texture(source, TexCoords + vec2(x, y) * texelSize).r;
+        }
+    }
+    Occlusion = result / float(NOISE_SIZE * NOISE_SIZE);
+}
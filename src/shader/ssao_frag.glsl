@@ -0,0 +1,58 @@
+#version 430 core
+#include "camera.glsl"
+
+in vec2 TexCoords;
+
+out float Occlusion;
+
+// View space normals and depth of the G-buffer.
+uniform sampler2D gNormal;
+uniform sampler2D gDepth;
+// Random rotations of the kernel around the normal, tiled over the screen.
+uniform sampler2D noise;
+// Offsets in the hemisphere around +z, see `kernel`.
+uniform vec3 samples[KERNEL_SIZE];
+uniform mat4 inverseProjection;
+uniform float radius;
+uniform float bias;
+
+// View space position of what the G-buffer holds at `uv`.
+vec3 ViewPos(vec2 uv)
+{
+    vec4 clip = vec4(vec3(uv, texture(gDepth, uv).r) * 2.0 - 1.0, 1.0);
+    vec4 viewPos = inverseProjection * clip;
+    return viewPos.xyz / viewPos.w;
+}
+
+// The share of the kernel's samples, oriented along the normal, that aren't behind the
+// geometry in the G-buffer.
+void main()
+{
+    if (texture(gDepth, TexCoords).r == 1.0) {
+        // Nothing was drawn here.
+        Occlusion = 1.0;
+        return;
+    }
+    vec3 fragPos = ViewPos(TexCoords);
+    vec3 normal = normalize(texture(gNormal, TexCoords).xyz);
+    vec2 noiseScale = vec2(textureSize(gDepth, 0)) / float(NOISE_SIZE);
+    vec3 randomVec = texture(noise, TexCoords * noiseScale).xyz;
+
+    // A tangent space around the normal, turned by the random vector.
+    vec3 tangent = normalize(randomVec - normal * dot(randomVec, normal));
+    vec3 bitangent = cross(normal, tangent);
+    mat3 tbn = mat3(tangent, bitangent, normal);
+
+    float occlusion = 0.0;
+    for (int i = 0; i < KERNEL_SIZE; i++) {
+        vec3 samplePos = fragPos + tbn * samples[i] * radius;
+        vec4 offset = projection * vec4(samplePos, 1.0);
+        vec2 sampleUv = offset.xy / offset.w * 0.5 + 0.5;
+        float sampleDepth = ViewPos(sampleUv).z;
+        // Geometry much further away than the radius is something else entirely, like the
+        // background behind an edge.
+        float rangeCheck = smoothstep(0.0, 1.0, radius / abs(fragPos.z - sampleDepth));
+        occlusion += (sampleDepth >= samplePos.z + bias ? 1.0 : 0.0) * rangeCheck;
+    }
+    Occlusion = 1.0 - occlusion / float(KERNEL_SIZE);
+}
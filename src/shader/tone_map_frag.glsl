@@ -0,0 +1,56 @@
+#version 430 core
+
+in vec2 TexCoords;
+
+out vec4 FragColor;
+
+uniform sampler2D hdrTexture;
+// The blurred bright parts of the scene, added on top of it.
+uniform sampler2D bloom;
+uniform float bloomIntensity;
+// Log luminance of the scene, averaged by its smallest mip level.
+uniform sampler2D luminance;
+// One of the TONE_MAP_* values defined from Rust.
+uniform int toneMapOperator;
+uniform float exposure;
+uniform bool autoExposure;
+
+// Where auto exposure puts the scene's average luminance.
+const float MIDDLE_GRAY = 0.18;
+
+// Krzysztof Narkowicz's fit of the ACES filmic curve.
+vec3 Aces(vec3 color)
+{
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+    return clamp((color * (a * color + b)) / (color * (c * color + d) + e), 0.0, 1.0);
+}
+
+void main()
+{
+    float scale = exposure;
+    if (autoExposure) {
+        float top = float(textureQueryLevels(luminance) - 1);
+        float average = exp(textureLod(luminance, vec2(0.5), top).r);
+        scale *= MIDDLE_GRAY / max(average, 0.0001);
+    }
+
+    vec3 hdr = texture(hdrTexture, TexCoords).rgb;
+    hdr += texture(bloom, TexCoords).rgb * bloomIntensity;
+    vec3 color = hdr * scale;
+    switch (toneMapOperator) {
+        case TONE_MAP_REINHARD:
+            color = color / (color + 1.0);
+            break;
+        case TONE_MAP_EXPOSURE:
+            color = 1.0 - exp(-color);
+            break;
+        case TONE_MAP_ACES:
+            color = Aces(color);
+            break;
+    }
+    FragColor = vec4(color, 1.0);
+}
@@ -0,0 +1,394 @@
+use std::{
+    any::Any,
+    array,
+    cell::{Cell, Ref, RefCell},
+    mem::size_of_val,
+    rc::Rc,
+};
+
+use glam::{IVec2, IVec3, IVec4, Mat3, Mat4, Quat, Vec2, Vec3, Vec4};
+
+use crate::gl::{
+    self,
+    types::{GLenum, GLint, GLsizei, GLuint},
+    Gl,
+};
+
+use super::{reflection, Shader, ShaderTrait, UniformError};
+
+/// A Rust type that can be uploaded to a uniform.
+pub trait UniformValue: Copy + 'static {
+    /// The GLSL type of uniforms this is set to.
+    const GL_TYPE: GLenum;
+    /// Number of array elements the value covers.
+    const LEN: usize = 1;
+
+    fn accepts(gl_type: GLenum) -> bool {
+        gl_type == Self::GL_TYPE
+    }
+
+    /// Sets `values` to consecutive array elements, starting at `location`.
+    ///
+    /// # Safety
+    /// `location` must be -1 or a location in `program` whose type `accepts`.
+    unsafe fn set(gl: &Gl, program: GLuint, location: GLint, values: &[Self]);
+
+    /// Reads the value back, given the location of each of its `LEN` array elements.
+    ///
+    /// # Safety
+    /// Same as `set`, for each of `locations`.
+    unsafe fn get(gl: &Gl, program: GLuint, locations: &[GLint]) -> Self;
+}
+
+/// The texture unit a sampler reads from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextureUnit(pub GLuint);
+
+macro_rules! scalar_uniform {
+    ($type:ty, $gl_type:expr, $set:ident, $get:ident) => {
+        impl UniformValue for $type {
+            const GL_TYPE: GLenum = $gl_type;
+
+            unsafe fn set(gl: &Gl, program: GLuint, location: GLint, values: &[Self]) {
+                gl.$set(program, location, values.len() as GLsizei, values.as_ptr());
+            }
+            unsafe fn get(gl: &Gl, program: GLuint, locations: &[GLint]) -> Self {
+                let mut param = <$type>::default();
+                gl.$get(
+                    program,
+                    locations[0],
+                    size_of_val(&param) as GLsizei,
+                    &mut param,
+                );
+                param
+            }
+        }
+    };
+}
+
+macro_rules! vector_uniform {
+    ($type:ty, $gl_type:expr, [$component:ty; $len:expr], $set:ident, $get:ident) => {
+        impl UniformValue for $type {
+            const GL_TYPE: GLenum = $gl_type;
+
+            unsafe fn set(gl: &Gl, program: GLuint, location: GLint, values: &[Self]) {
+                gl.$set(
+                    program,
+                    location,
+                    values.len() as GLsizei,
+                    values.as_ptr().cast(),
+                );
+            }
+            unsafe fn get(gl: &Gl, program: GLuint, locations: &[GLint]) -> Self {
+                let mut params: [$component; $len] = Default::default();
+                gl.$get(
+                    program,
+                    locations[0],
+                    size_of_val(&params) as GLsizei,
+                    params.as_mut_ptr(),
+                );
+                Self::from(params)
+            }
+        }
+    };
+}
+
+macro_rules! matrix_uniform {
+    ($type:ty, $gl_type:expr, $len:expr, $set:ident) => {
+        impl UniformValue for $type {
+            const GL_TYPE: GLenum = $gl_type;
+
+            unsafe fn set(gl: &Gl, program: GLuint, location: GLint, values: &[Self]) {
+                gl.$set(
+                    program,
+                    location,
+                    values.len() as GLsizei,
+                    gl::FALSE,
+                    values.as_ptr().cast(),
+                );
+            }
+            unsafe fn get(gl: &Gl, program: GLuint, locations: &[GLint]) -> Self {
+                let mut params = [0.0_f32; $len];
+                gl.GetnUniformfv(
+                    program,
+                    locations[0],
+                    size_of_val(&params) as GLsizei,
+                    params.as_mut_ptr(),
+                );
+                Self::from_cols_slice(&params)
+            }
+        }
+    };
+}
+
+// glam's vectors and matrices are tightly packed `f32`/`i32` components, so slices of them can
+// be passed to GL as is.
+scalar_uniform!(f32, gl::FLOAT, ProgramUniform1fv, GetnUniformfv);
+scalar_uniform!(u32, gl::UNSIGNED_INT, ProgramUniform1uiv, GetnUniformuiv);
+vector_uniform!(
+    Vec2,
+    gl::FLOAT_VEC2,
+    [f32; 2],
+    ProgramUniform2fv,
+    GetnUniformfv
+);
+vector_uniform!(
+    Vec3,
+    gl::FLOAT_VEC3,
+    [f32; 3],
+    ProgramUniform3fv,
+    GetnUniformfv
+);
+vector_uniform!(
+    Vec4,
+    gl::FLOAT_VEC4,
+    [f32; 4],
+    ProgramUniform4fv,
+    GetnUniformfv
+);
+vector_uniform!(
+    IVec2,
+    gl::INT_VEC2,
+    [i32; 2],
+    ProgramUniform2iv,
+    GetnUniformiv
+);
+vector_uniform!(
+    IVec3,
+    gl::INT_VEC3,
+    [i32; 3],
+    ProgramUniform3iv,
+    GetnUniformiv
+);
+vector_uniform!(
+    IVec4,
+    gl::INT_VEC4,
+    [i32; 4],
+    ProgramUniform4iv,
+    GetnUniformiv
+);
+matrix_uniform!(Mat3, gl::FLOAT_MAT3, 9, ProgramUniformMatrix3fv);
+matrix_uniform!(Mat4, gl::FLOAT_MAT4, 16, ProgramUniformMatrix4fv);
+
+impl UniformValue for i32 {
+    const GL_TYPE: GLenum = gl::INT;
+
+    /// Like `glUniform1i`, also accepts bools and samplers.
+    fn accepts(gl_type: GLenum) -> bool {
+        reflection::accepts(gl::INT, gl_type)
+    }
+
+    unsafe fn set(gl: &Gl, program: GLuint, location: GLint, values: &[Self]) {
+        gl.ProgramUniform1iv(program, location, values.len() as GLsizei, values.as_ptr());
+    }
+    unsafe fn get(gl: &Gl, program: GLuint, locations: &[GLint]) -> Self {
+        let mut param: GLint = 0;
+        gl.GetnUniformiv(
+            program,
+            locations[0],
+            size_of_val(&param) as GLsizei,
+            &mut param,
+        );
+        param
+    }
+}
+
+impl UniformValue for bool {
+    const GL_TYPE: GLenum = gl::BOOL;
+
+    unsafe fn set(gl: &Gl, program: GLuint, location: GLint, values: &[Self]) {
+        let values: Vec<GLint> = values.iter().map(|&value| value.into()).collect();
+        i32::set(gl, program, location, &values);
+    }
+    unsafe fn get(gl: &Gl, program: GLuint, locations: &[GLint]) -> Self {
+        i32::get(gl, program, locations) != 0
+    }
+}
+
+impl UniformValue for TextureUnit {
+    const GL_TYPE: GLenum = gl::SAMPLER_2D;
+
+    fn accepts(gl_type: GLenum) -> bool {
+        reflection::is_sampler(gl_type)
+    }
+
+    unsafe fn set(gl: &Gl, program: GLuint, location: GLint, values: &[Self]) {
+        let values: Vec<GLint> = values.iter().map(|unit| unit.0 as GLint).collect();
+        i32::set(gl, program, location, &values);
+    }
+    unsafe fn get(gl: &Gl, program: GLuint, locations: &[GLint]) -> Self {
+        TextureUnit(i32::get(gl, program, locations) as GLuint)
+    }
+}
+
+/// Rotations are uploaded as a `mat3`.
+impl UniformValue for Quat {
+    const GL_TYPE: GLenum = gl::FLOAT_MAT3;
+
+    unsafe fn set(gl: &Gl, program: GLuint, location: GLint, values: &[Self]) {
+        let values: Vec<Mat3> = values.iter().map(|&quat| Mat3::from_quat(quat)).collect();
+        Mat3::set(gl, program, location, &values);
+    }
+    unsafe fn get(gl: &Gl, program: GLuint, locations: &[GLint]) -> Self {
+        Quat::from_mat3(&Mat3::get(gl, program, locations))
+    }
+}
+
+impl<T: UniformValue, const N: usize> UniformValue for [T; N] {
+    const GL_TYPE: GLenum = T::GL_TYPE;
+    const LEN: usize = N * T::LEN;
+
+    fn accepts(gl_type: GLenum) -> bool {
+        T::accepts(gl_type)
+    }
+
+    unsafe fn set(gl: &Gl, program: GLuint, location: GLint, values: &[Self]) {
+        T::set(gl, program, location, values.as_flattened());
+    }
+    unsafe fn get(gl: &Gl, program: GLuint, locations: &[GLint]) -> Self {
+        array::from_fn(|index| unsafe {
+            T::get(
+                gl,
+                program,
+                &locations[index * T::LEN..(index + 1) * T::LEN],
+            )
+        })
+    }
+}
+
+/// The last value set to a uniform, shared by every handle to it and replayed after a reload
+/// since a freshly linked program starts with every uniform zeroed.
+pub(super) trait UniformSlot {
+    /// Sets the last value, if there is one, to `location` of `program`.
+    fn replay(&self, gl: &Gl, program: GLuint, location: GLint);
+    fn as_any(self: Rc<Self>) -> Rc<dyn Any>;
+}
+
+pub(super) struct LastValue<T>(Cell<Option<T>>);
+
+impl<T> Default for LastValue<T> {
+    fn default() -> Self {
+        Self(Cell::new(None))
+    }
+}
+
+impl<T: UniformValue> LastValue<T> {
+    pub(super) fn set(&self, val: T) {
+        self.0.set(Some(val));
+    }
+}
+
+impl<T: UniformValue> UniformSlot for LastValue<T> {
+    fn replay(&self, gl: &Gl, program: GLuint, location: GLint) {
+        if let Some(val) = self.0.get() {
+            unsafe { T::set(gl, program, location, &[val]) };
+        }
+    }
+    fn as_any(self: Rc<Self>) -> Rc<dyn Any> {
+        self
+    }
+}
+
+/// A typed handle to a uniform of a `Shader`. Its location is looked up again whenever the
+/// shader is reloaded.
+pub struct Uniform<T> {
+    gl: Gl,
+    shader: Shader,
+    name: String,
+    /// Location of each array element in the current program, -1 if a reload removed it.
+    locations: RefCell<Vec<GLint>>,
+    generation: Cell<u32>,
+    last_value: Rc<LastValue<T>>,
+}
+
+impl<T: UniformValue> Uniform<T> {
+    pub fn new(gl: &Gl, shader: &Shader, name: &str) -> Result<Self, UniformError> {
+        let locations = shader.uniform_locations::<T>(name)?;
+        Ok(Self {
+            gl: gl.clone(),
+            shader: shader.clone(),
+            name: name.to_string(),
+            locations: RefCell::new(locations),
+            generation: Cell::new(shader.generation()),
+            last_value: shader.last_value(name),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn locations(&self) -> Ref<'_, Vec<GLint>> {
+        if self.generation.get() != self.shader.generation() {
+            let locations = self
+                .shader
+                .uniform_locations::<T>(&self.name)
+                .unwrap_or_else(|_| vec![-1; T::LEN]);
+            self.locations.replace(locations);
+            self.generation.set(self.shader.generation());
+        }
+        self.locations.borrow()
+    }
+}
+
+/// A Rust struct bound to a GLSL struct uniform, usually through `#[derive(ShaderUniforms)]`.
+/// Each field is set to the member of the same name, and `uniforms` fails if the shader has a
+/// member without a matching field.
+/// ```
+/// use glam::Vec3;
+/// use learn_ogl_rs::shader::ShaderUniforms;
+///
+/// #[derive(ShaderUniforms)]
+/// struct Spot {
+///     direction: Vec3,
+///     #[uniform(rename = "cutOff", with = f32::cos)]
+///     cutoff: f32,
+///     #[uniform(skip)]
+///     _label: &'static str,
+/// }
+///
+/// assert_eq!(Spot::UNIFORM_NAMES, ["direction", "cutOff"]);
+/// ```
+/// Fields have to be a `UniformValue`, or be skipped.
+/// ```compile_fail
+/// # use learn_ogl_rs::shader::ShaderUniforms;
+/// #[derive(ShaderUniforms)]
+/// struct Spot {
+///     label: String,
+/// }
+/// ```
+/// ```compile_fail
+/// # use learn_ogl_rs::shader::ShaderUniforms;
+/// #[derive(ShaderUniforms)]
+/// struct Spot {
+///     #[uniform(renamed = "cutOff")]
+///     cutoff: f32,
+/// }
+/// ```
+pub trait ShaderUniforms {
+    /// The handles to each bound member.
+    type Uniforms;
+    /// GLSL name of each bound member.
+    const UNIFORM_NAMES: &'static [&'static str];
+
+    /// Looks up the members of the struct uniform `prefix`, like `spotLight` or
+    /// `pointLights[2]`.
+    fn uniforms(gl: &Gl, shader: &Shader, prefix: &str) -> Result<Self::Uniforms, UniformError>;
+    fn upload(&self, uniforms: &Self::Uniforms);
+}
+
+pub trait UniformGetSet<T> {
+    fn get(&self) -> T;
+    fn set(&self, val: T);
+}
+
+impl<T: UniformValue> UniformGetSet<T> for Uniform<T> {
+    fn get(&self) -> T {
+        unsafe { T::get(&self.gl, self.shader.get_id(), &self.locations()) }
+    }
+    fn set(&self, val: T) {
+        let location = self.locations()[0];
+        unsafe { T::set(&self.gl, self.shader.get_id(), location, &[val]) };
+        self.last_value.set(val);
+    }
+}
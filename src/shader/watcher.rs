@@ -0,0 +1,68 @@
+use std::time::{Duration, Instant};
+
+use crate::gl::Gl;
+
+use super::Shader;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Reloads shaders whose source files changed on disk. A shader that fails to compile keeps
+/// running its previous program and the error is printed instead.
+pub struct ShaderWatcher {
+    shaders: Vec<Shader>,
+    last_poll: Instant,
+}
+
+impl Default for ShaderWatcher {
+    fn default() -> Self {
+        Self {
+            shaders: vec![],
+            last_poll: Instant::now(),
+        }
+    }
+}
+
+impl ShaderWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn watch(&mut self, shader: &Shader) {
+        if !self
+            .shaders
+            .iter()
+            .any(|watched| watched.same_program(shader))
+        {
+            self.shaders.push(shader.clone());
+        }
+    }
+
+    /// Checks the watched files for changes, at most every `POLL_INTERVAL`. Call once a frame.
+    pub fn poll(&mut self, gl: &Gl) {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return;
+        }
+        self.last_poll = Instant::now();
+
+        for shader in self
+            .shaders
+            .iter()
+            .filter(|shader| shader.sources_changed())
+        {
+            match shader.reload(gl) {
+                Ok(()) => {
+                    // The files `sources_changed` watches, every stage and what it includes.
+                    let sources: Vec<_> = shader
+                        .program
+                        .sources
+                        .borrow()
+                        .iter()
+                        .map(|path| path.display().to_string())
+                        .collect();
+                    println!("Reloaded {}", sources.join(", "));
+                }
+                Err(err) => eprintln!("{err}"),
+            }
+        }
+    }
+}
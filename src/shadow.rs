@@ -0,0 +1,294 @@
+mod atlas;
+mod directional;
+mod point;
+mod spot;
+
+use glam::{Mat4, Vec4};
+
+use crate::{
+    gl::{
+        self,
+        types::{GLenum, GLuint},
+        Gl,
+    },
+    renderer::framebuffer::FramebufferError,
+    shader::{BufferBlock, Std140, SHADOW_BINDING},
+};
+
+pub use self::atlas::{AtlasTile, ShadowAtlas};
+pub use self::directional::{cascade_splits, light_space_matrix, DirectionalShadow, MAX_CASCADES};
+pub use self::point::{cube_face_matrices, PointShadows, MAX_POINT_SHADOWS};
+pub use self::spot::SpotShadows;
+
+// Texture units lit programs sample the shadow maps from.
+pub const DIRECTIONAL_SHADOW_UNIT: GLuint = 2;
+pub const POINT_SHADOW_UNIT: GLuint = 3;
+pub const SPOT_SHADOW_UNIT: GLuint = 4;
+
+const RESOLUTION_DEFAULT: u32 = 2048;
+const BIAS_DEFAULT: f32 = 0.0005;
+const PCF_RADIUS_DEFAULT: i32 = 1;
+const DISTANCE_DEFAULT: f32 = 50.0;
+const CASCADES_DEFAULT: u32 = 4;
+const SPLIT_LAMBDA_DEFAULT: f32 = 0.75;
+
+/// Settings of the directional light's cascaded shadow maps.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowSettings {
+    /// Width and height of each cascade's shadow map in texels.
+    pub resolution: u32,
+    /// Depth bias for surfaces facing the light, surfaces at grazing angles get up to ten times
+    /// as much.
+    pub bias: f32,
+    /// Texels sampled in each direction around a fragment, 0 samples only the one under it.
+    pub pcf_radius: i32,
+    /// How far from the camera shadows reach. The cascades are fitted to the part of the view
+    /// frustum closer than this, so shorter distances give sharper shadows.
+    pub distance: f32,
+    /// Number of shadow maps the view frustum is split into, up to `MAX_CASCADES`. Each one
+    /// covers a slice further from the camera than the last.
+    pub cascades: u32,
+    /// Where the slices are split, from evenly spaced at 0 to logarithmically spaced at 1. See
+    /// `cascade_splits`.
+    pub split_lambda: f32,
+    /// Tints every fragment by the cascade its shadow is looked up in.
+    pub debug_cascades: bool,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            resolution: RESOLUTION_DEFAULT,
+            bias: BIAS_DEFAULT,
+            pcf_radius: PCF_RADIUS_DEFAULT,
+            distance: DISTANCE_DEFAULT,
+            cascades: CASCADES_DEFAULT,
+            split_lambda: SPLIT_LAMBDA_DEFAULT,
+            debug_cascades: false,
+        }
+    }
+}
+
+/// The `ShadowBlock` uniform block, describing the cascades of the directional light casting
+/// shadows.
+#[derive(Clone, Copy, Std140, BufferBlock)]
+#[repr(C)]
+#[block(binding = SHADOW_BINDING)]
+pub struct ShadowBlock {
+    #[uniform(rename = "lightSpaceMatrices")]
+    pub light_space_matrices: [Mat4; MAX_CASCADES],
+    /// Distance from the camera at which each cascade ends.
+    #[uniform(rename = "cascadeSplits")]
+    pub cascade_splits: Vec4,
+    /// Index of the light among the enabled directional lights, -1 if none casts shadows.
+    #[uniform(rename = "shadowLight")]
+    pub shadow_light: i32,
+    #[uniform(rename = "shadowBias")]
+    pub bias: f32,
+    #[uniform(rename = "pcfRadius")]
+    pub pcf_radius: i32,
+    #[uniform(rename = "cascadeCount")]
+    pub cascade_count: i32,
+    #[uniform(rename = "debugCascades")]
+    pub debug_cascades: u32,
+    #[uniform(skip)]
+    _padding: [f32; 3],
+}
+
+impl Default for ShadowBlock {
+    fn default() -> Self {
+        Self {
+            light_space_matrices: [Mat4::IDENTITY; MAX_CASCADES],
+            cascade_splits: Vec4::ZERO,
+            shadow_light: -1,
+            bias: BIAS_DEFAULT,
+            pcf_radius: PCF_RADIUS_DEFAULT,
+            cascade_count: 0,
+            debug_cascades: 0,
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+/// The kind of depth texture a `ShadowMap` renders into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShadowMapKind {
+    /// A single 2D texture, sampled with a `sampler2DShadow`.
+    Flat,
+    /// A 2D texture array of this many layers, sampled with a `sampler2DArrayShadow`.
+    Array(u32),
+    /// An array of this many cube maps, sampled with a `samplerCubeArrayShadow`. Every face is
+    /// a layer of its own, rendered into by setting `gl_Layer` to `6 * cube + face`.
+    CubeArray(u32),
+}
+
+impl ShadowMapKind {
+    fn target(self) -> GLenum {
+        match self {
+            ShadowMapKind::Flat => gl::TEXTURE_2D,
+            ShadowMapKind::Array(_) => gl::TEXTURE_2D_ARRAY,
+            ShadowMapKind::CubeArray(_) => gl::TEXTURE_CUBE_MAP_ARRAY,
+        }
+    }
+}
+
+/// Square depth textures and the framebuffer rendering into them. The textures compare against
+/// a reference depth when sampled, for shadow samplers.
+pub struct ShadowMap {
+    gl: Gl,
+    kind: ShadowMapKind,
+    framebuffer: GLuint,
+    texture: GLuint,
+    resolution: u32,
+}
+
+impl ShadowMap {
+    /// Fails if the depth format or the number of layers isn't supported.
+    pub fn new(gl: &Gl, kind: ShadowMapKind, resolution: u32) -> Result<Self, FramebufferError> {
+        let mut map = Self {
+            gl: gl.clone(),
+            kind,
+            framebuffer: 0,
+            texture: 0,
+            resolution,
+        };
+        map.allocate()?;
+        Ok(map)
+    }
+
+    pub fn texture(&self) -> GLuint {
+        self.texture
+    }
+    pub fn resolution(&self) -> u32 {
+        self.resolution
+    }
+
+    /// Reallocates the texture if `resolution` changed, discarding its contents.
+    pub fn resize(&mut self, resolution: u32) -> Result<(), FramebufferError> {
+        self.reallocate(self.kind, resolution)
+    }
+
+    /// Like `resize`, for a change of `kind` and `resolution` at once. The map is left as it
+    /// was if the new one can't be allocated.
+    pub fn reallocate(
+        &mut self,
+        kind: ShadowMapKind,
+        resolution: u32,
+    ) -> Result<(), FramebufferError> {
+        if kind != self.kind || resolution != self.resolution {
+            *self = Self::new(&self.gl, kind, resolution)?;
+        }
+        Ok(())
+    }
+
+    /// Runs `draw` with the shadow map as the render target, every layer of it cleared, then
+    /// restores the framebuffer and the viewport that were set before.
+    pub fn render(&self, draw: impl FnOnce()) {
+        let gl = &self.gl;
+        let mut viewport = [0; 4];
+        let mut previous = 0;
+        unsafe {
+            gl.GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+            gl.GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut previous);
+            gl.BindFramebuffer(gl::FRAMEBUFFER, self.framebuffer);
+            gl.Viewport(0, 0, self.resolution as i32, self.resolution as i32);
+            gl.Clear(gl::DEPTH_BUFFER_BIT);
+        }
+        draw();
+        unsafe {
+            gl.BindFramebuffer(gl::FRAMEBUFFER, previous as GLuint);
+            gl.Viewport(viewport[0], viewport[1], viewport[2], viewport[3]);
+        }
+    }
+
+    /// Like `render`, rendering into the first `layers` layers one at a time. `draw` is called
+    /// with the index of each layer once it's the render target.
+    pub fn render_layers(&self, layers: u32, mut draw: impl FnMut(u32)) {
+        let gl = &self.gl;
+        self.render(|| {
+            for layer in 0..layers {
+                unsafe {
+                    gl.NamedFramebufferTextureLayer(
+                        self.framebuffer,
+                        gl::DEPTH_ATTACHMENT,
+                        self.texture,
+                        0,
+                        layer as i32,
+                    );
+                }
+                draw(layer);
+            }
+            // Back to every layer at once, for `render`.
+            unsafe {
+                gl.NamedFramebufferTexture(self.framebuffer, gl::DEPTH_ATTACHMENT, self.texture, 0)
+            };
+        });
+    }
+
+    /// Creates the texture and framebuffer, which `drop` deletes again if they're incomplete.
+    fn allocate(&mut self) -> Result<(), FramebufferError> {
+        let gl = &self.gl;
+        let size = self.resolution as i32;
+        unsafe {
+            gl.CreateTextures(self.kind.target(), 1, &mut self.texture);
+            match self.kind {
+                ShadowMapKind::Flat => {
+                    gl.TextureStorage2D(self.texture, 1, gl::DEPTH_COMPONENT32F, size, size)
+                }
+                ShadowMapKind::Array(layers) => gl.TextureStorage3D(
+                    self.texture,
+                    1,
+                    gl::DEPTH_COMPONENT32F,
+                    size,
+                    size,
+                    layers as i32,
+                ),
+                ShadowMapKind::CubeArray(cubes) => gl.TextureStorage3D(
+                    self.texture,
+                    1,
+                    gl::DEPTH_COMPONENT32F,
+                    size,
+                    size,
+                    6 * cubes as i32,
+                ),
+            }
+            // Linear filtering of a comparing texture averages the comparisons of the four
+            // nearest texels, which smooths the edges PCF leaves.
+            gl.TextureParameteri(self.texture, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl.TextureParameteri(self.texture, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            // Everything outside of the map is lit. Cube maps ignore wrapping.
+            gl.TextureParameteri(self.texture, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_BORDER as i32);
+            gl.TextureParameteri(self.texture, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_BORDER as i32);
+            gl.TextureParameterfv(
+                self.texture,
+                gl::TEXTURE_BORDER_COLOR,
+                [1.0_f32; 4].as_ptr(),
+            );
+            gl.TextureParameteri(
+                self.texture,
+                gl::TEXTURE_COMPARE_MODE,
+                gl::COMPARE_REF_TO_TEXTURE as i32,
+            );
+            gl.TextureParameteri(self.texture, gl::TEXTURE_COMPARE_FUNC, gl::LEQUAL as i32);
+
+            gl.CreateFramebuffers(1, &mut self.framebuffer);
+            gl.NamedFramebufferTexture(self.framebuffer, gl::DEPTH_ATTACHMENT, self.texture, 0);
+            gl.NamedFramebufferDrawBuffer(self.framebuffer, gl::NONE);
+            gl.NamedFramebufferReadBuffer(self.framebuffer, gl::NONE);
+            let status = gl.CheckNamedFramebufferStatus(self.framebuffer, gl::FRAMEBUFFER);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(FramebufferError::Incomplete(status));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ShadowMap {
+    fn drop(&mut self) {
+        unsafe {
+            self.gl.DeleteFramebuffers(1, &self.framebuffer);
+            self.gl.DeleteTextures(1, &self.texture);
+        }
+    }
+}
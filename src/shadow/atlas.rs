@@ -0,0 +1,134 @@
+use glam::{uvec2, vec4, UVec2, Vec4};
+
+/// A square region of a `ShadowAtlas`, in texels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasTile {
+    pub offset: UVec2,
+    pub size: u32,
+}
+
+impl AtlasTile {
+    /// The tile's offset and size as fractions of an atlas `atlas_size` texels wide, packed into
+    /// `xy` and `zw` for shaders to map a shadow map lookup into the tile.
+    pub fn uv_rect(&self, atlas_size: u32) -> Vec4 {
+        let atlas_size = atlas_size as f32;
+        vec4(
+            self.offset.x as f32 / atlas_size,
+            self.offset.y as f32 / atlas_size,
+            self.size as f32 / atlas_size,
+            self.size as f32 / atlas_size,
+        )
+    }
+}
+
+/// Hands out square tiles of one shadow map texture so several lights can share it. Tiles are
+/// powers of two, made by splitting bigger free tiles in four, so none ever overlap. The
+/// atlas is cleared and filled again every frame.
+/// ```
+/// # use learn_ogl_rs::shadow::ShadowAtlas;
+/// let mut atlas = ShadowAtlas::new(2048);
+/// let big = atlas.allocate(1024).unwrap();
+/// let small = atlas.allocate(500).unwrap();
+/// assert_eq!((big.offset.x, big.size), (0, 1024));
+/// assert_eq!((small.offset.x, small.size), (1024, 512));
+/// assert_eq!(atlas.allocate(4096), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ShadowAtlas {
+    size: u32,
+    /// Offsets of the free tiles of each size, the whole atlas first and every next level half
+    /// as wide.
+    free: Vec<Vec<UVec2>>,
+}
+
+impl ShadowAtlas {
+    /// `size` is rounded up to a power of two.
+    pub fn new(size: u32) -> Self {
+        let mut atlas = Self {
+            size: size.next_power_of_two(),
+            free: vec![],
+        };
+        atlas.clear();
+        atlas
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Frees every tile.
+    pub fn clear(&mut self) {
+        self.free = vec![vec![UVec2::ZERO]];
+    }
+
+    /// A free tile at least `size` texels wide, `None` if the atlas has no room left for one.
+    pub fn allocate(&mut self, size: u32) -> Option<AtlasTile> {
+        let size = size.max(1).next_power_of_two();
+        if size > self.size {
+            return None;
+        }
+        let level = (self.size / size).trailing_zeros() as usize;
+        if self.free.len() <= level {
+            self.free.resize(level + 1, vec![]);
+        }
+
+        // Split the smallest free tile that's big enough until it's the right size, keeping
+        // the other three quarters.
+        let mut found = (0..=level)
+            .rev()
+            .find(|&level| !self.free[level].is_empty())?;
+        // Tiles are taken in offset order so the atlas fills up predictably.
+        let (index, _) = self.free[found]
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, free)| (free.y, free.x))?;
+        let offset = self.free[found].swap_remove(index);
+        while found < level {
+            found += 1;
+            let half = self.size >> found;
+            self.free[found].extend([
+                offset + uvec2(half, 0),
+                offset + uvec2(0, half),
+                offset + uvec2(half, half),
+            ]);
+        }
+        Some(AtlasTile { offset, size })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tiles_dont_overlap() {
+        let mut atlas = ShadowAtlas::new(1024);
+        let mut tiles = vec![];
+        for size in [256, 512, 128, 512, 256, 512] {
+            tiles.push(atlas.allocate(size).unwrap());
+        }
+        // What's left is a quarter of one 512 tile and three quarters of a quarter of it.
+        assert_eq!(atlas.allocate(512), None);
+        for size in [256, 128, 128, 128] {
+            tiles.push(atlas.allocate(size).unwrap());
+        }
+        assert_eq!(atlas.allocate(1), None);
+
+        for (index, a) in tiles.iter().enumerate() {
+            assert!((a.offset + a.size).max_element() <= 1024);
+            for b in &tiles[index + 1..] {
+                let overlaps = a.offset.x < b.offset.x + b.size
+                    && b.offset.x < a.offset.x + a.size
+                    && a.offset.y < b.offset.y + b.size
+                    && b.offset.y < a.offset.y + a.size;
+                assert!(!overlaps, "{a:?} overlaps {b:?}");
+            }
+        }
+
+        atlas.clear();
+        assert_eq!(
+            atlas.allocate(1024).map(|tile| tile.offset),
+            Some(UVec2::ZERO)
+        );
+    }
+}
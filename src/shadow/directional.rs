@@ -0,0 +1,192 @@
+use std::rc::Rc;
+
+use glam::{Mat4, Vec3};
+
+use crate::{
+    camera::{Camera, FAR_PLANE, NEAR_PLANE},
+    gl::Gl,
+    renderer::framebuffer::FramebufferError,
+    shader::{DepthShader, UniformBuffer, UniformGetSet},
+};
+
+use super::{ShadowBlock, ShadowMap, ShadowMapKind, ShadowSettings, DIRECTIONAL_SHADOW_UNIT};
+
+/// Most cascades `ShadowSettings::cascades` can ask for.
+pub const MAX_CASCADES: usize = 4;
+
+/// How far the light's frustum extends toward the light past the camera's frustum, so objects
+/// out of view still cast shadows into it.
+const CASTER_MARGIN: f32 = 20.0;
+
+/// The cascaded shadow maps of the one directional light casting shadows, one layer of a
+/// texture array per cascade.
+pub struct DirectionalShadow {
+    map: ShadowMap,
+    settings: ShadowSettings,
+    block: UniformBuffer<ShadowBlock>,
+    depth_shader: Rc<DepthShader>,
+}
+
+impl DirectionalShadow {
+    pub fn new(
+        gl: &Gl,
+        settings: ShadowSettings,
+        depth_shader: Rc<DepthShader>,
+    ) -> Result<Self, FramebufferError> {
+        let settings = ShadowSettings {
+            cascades: settings.cascades.clamp(1, MAX_CASCADES as u32),
+            ..settings
+        };
+        Ok(Self {
+            map: ShadowMap::new(
+                gl,
+                ShadowMapKind::Array(settings.cascades),
+                settings.resolution,
+            )?,
+            settings,
+            block: UniformBuffer::new(gl, ShadowBlock::default()),
+            depth_shader,
+        })
+    }
+
+    pub fn settings(&self) -> ShadowSettings {
+        self.settings
+    }
+    /// The number of cascades is clamped to `1..=MAX_CASCADES`. The settings are kept as they
+    /// were if the shadow maps can't be reallocated for them.
+    pub fn set_settings(&mut self, settings: ShadowSettings) -> Result<(), FramebufferError> {
+        let cascades = settings.cascades.clamp(1, MAX_CASCADES as u32);
+        self.map
+            .reallocate(ShadowMapKind::Array(cascades), settings.resolution)?;
+        self.settings = ShadowSettings {
+            cascades,
+            ..settings
+        };
+        Ok(())
+    }
+
+    /// Renders a shadow map of the light pointing in `light_dir` for each cascade of `camera`'s
+    /// view frustum. `light_index` is the light's index among the enabled directional lights,
+    /// and `draw` draws every shadow caster with the shader it's given.
+    ///
+    /// Passing no light turns shadows off.
+    pub fn render(
+        &self,
+        gl: &Gl,
+        light: Option<(usize, Vec3)>,
+        camera: &Camera,
+        aspect_ratio: f32,
+        draw: impl Fn(&DepthShader),
+    ) {
+        let Some((light_index, light_dir)) = light else {
+            self.block.set(ShadowBlock::default());
+            return;
+        };
+
+        let settings = &self.settings;
+        let far = settings.distance.clamp(NEAR_PLANE, FAR_PLANE);
+        let splits = cascade_splits(NEAR_PLANE, far, settings.cascades, settings.split_lambda);
+        let mut block = ShadowBlock {
+            shadow_light: light_index as i32,
+            bias: settings.bias,
+            pcf_radius: settings.pcf_radius,
+            cascade_count: splits.len() as i32,
+            debug_cascades: settings.debug_cascades.into(),
+            ..Default::default()
+        };
+        let mut cascade_near = NEAR_PLANE;
+        for (cascade, &cascade_far) in splits.iter().enumerate() {
+            let frustum = camera.frustum_corners(aspect_ratio, cascade_near, cascade_far);
+            block.light_space_matrices[cascade] = light_space_matrix(light_dir, &frustum);
+            block.cascade_splits[cascade] = cascade_far;
+            cascade_near = cascade_far;
+        }
+
+        self.map.render_layers(splits.len() as u32, |cascade| {
+            self.depth_shader
+                .light_space
+                .set(block.light_space_matrices[cascade as usize]);
+            draw(&self.depth_shader);
+        });
+        unsafe { gl.BindTextureUnit(DIRECTIONAL_SHADOW_UNIT, self.map.texture()) };
+        self.block.set(block);
+    }
+}
+
+/// Distance from the camera at which each of `count` cascades between `near` and `far` ends.
+/// `lambda` blends between evenly spaced splits at 0, and logarithmically spaced ones at 1
+/// that give nearby cascades more of the shadow map's resolution.
+pub fn cascade_splits(near: f32, far: f32, count: u32, lambda: f32) -> Vec<f32> {
+    (1..=count)
+        .map(|cascade| {
+            let fraction = cascade as f32 / count as f32;
+            let uniform = near + (far - near) * fraction;
+            let logarithmic = near * (far / near).powf(fraction);
+            lambda * logarithmic + (1.0 - lambda) * uniform
+        })
+        .collect()
+}
+
+/// An orthographic projection looking down `light_dir` that fits `frustum` as tightly as it
+/// can, extended toward the light by `CASTER_MARGIN`.
+pub fn light_space_matrix(light_dir: Vec3, frustum: &[Vec3; 8]) -> Mat4 {
+    let light_dir = light_dir.normalize();
+    let center = frustum.iter().sum::<Vec3>() / frustum.len() as f32;
+    let up = if light_dir.y.abs() > 0.99 {
+        Vec3::Z
+    } else {
+        Vec3::Y
+    };
+    let view = Mat4::look_to_rh(center, light_dir, up);
+
+    let (min, max) = frustum
+        .iter()
+        .map(|&corner| view.transform_point3(corner))
+        .fold((Vec3::MAX, Vec3::MIN), |(min, max), corner| {
+            (min.min(corner), max.max(corner))
+        });
+    // View space looks down -z, so the nearest corner has the largest z.
+    let projection =
+        Mat4::orthographic_rh_gl(min.x, max.x, min.y, max.y, -max.z - CASTER_MARGIN, -min.z);
+    projection * view
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec3;
+
+    use super::*;
+
+    #[test]
+    fn test_cascade_splits() {
+        assert_eq!(cascade_splits(1.0, 9.0, 2, 0.0), [5.0, 9.0]);
+        assert_eq!(cascade_splits(1.0, 9.0, 2, 1.0), [3.0, 9.0]);
+        assert_eq!(cascade_splits(1.0, 9.0, 2, 0.5), [4.0, 9.0]);
+    }
+
+    #[test]
+    fn test_light_space_matrix_fits_frustum() {
+        let frustum = [
+            vec3(-1.0, -1.0, -1.0),
+            vec3(1.0, -1.0, -1.0),
+            vec3(-1.0, 1.0, -1.0),
+            vec3(1.0, 1.0, -1.0),
+            vec3(-4.0, -4.0, -10.0),
+            vec3(4.0, -4.0, -10.0),
+            vec3(-4.0, 4.0, -10.0),
+            vec3(4.0, 4.0, -10.0),
+        ];
+        let matrix = light_space_matrix(vec3(-0.2, -1.0, -0.3), &frustum);
+
+        for corner in frustum {
+            let ndc = matrix.project_point3(corner);
+            assert!(
+                ndc.abs().max_element() <= 1.0 + 1e-4,
+                "{corner} maps to {ndc}"
+            );
+        }
+        // A caster just above the frustum, between it and the light, is still in the map.
+        let caster = matrix.project_point3(vec3(0.0, 10.0, -5.0));
+        assert!(caster.z >= -1.0, "caster maps to {caster}");
+    }
+}
@@ -0,0 +1,94 @@
+use std::rc::Rc;
+
+use glam::{Mat4, Vec3};
+
+use crate::{
+    gl::Gl,
+    light::{Light, PointLights},
+    renderer::framebuffer::FramebufferError,
+    shader::{PointDepthShader, UniformGetSet},
+};
+
+use super::{ShadowMap, ShadowMapKind, POINT_SHADOW_UNIT};
+
+/// Most point lights that cast shadows at once, the rest are lit as if they didn't.
+pub const MAX_POINT_SHADOWS: u32 = 4;
+
+const NEAR_PLANE: f32 = 0.1;
+
+/// Cube shadow maps of the point lights casting shadows, one layer of a cube map array each.
+pub struct PointShadows {
+    gl: Gl,
+    map: ShadowMap,
+    depth_shader: Rc<PointDepthShader>,
+}
+
+impl PointShadows {
+    pub fn new(
+        gl: &Gl,
+        resolution: u32,
+        depth_shader: Rc<PointDepthShader>,
+    ) -> Result<Self, FramebufferError> {
+        Ok(Self {
+            gl: gl.clone(),
+            map: ShadowMap::new(gl, ShadowMapKind::CubeArray(MAX_POINT_SHADOWS), resolution)?,
+            depth_shader,
+        })
+    }
+
+    pub fn resolution(&self) -> u32 {
+        self.map.resolution()
+    }
+    pub fn set_resolution(&mut self, resolution: u32) -> Result<(), FramebufferError> {
+        self.map.resize(resolution)
+    }
+
+    /// Renders the shadows of the enabled lights that cast them and tells each light which
+    /// cube its shadow is in, so call it before uploading `lights`. `draw` draws every shadow
+    /// caster with the shader it's given.
+    pub fn render(&self, lights: &mut PointLights, draw: impl Fn(&PointDepthShader)) {
+        let mut layers = 0..MAX_POINT_SHADOWS;
+        let shader = &self.depth_shader;
+        self.map.render(|| {
+            for light in lights.iter_mut() {
+                let layer = if light.enabled() && light.casts_shadow() {
+                    layers.next()
+                } else {
+                    None
+                };
+                light.set_shadow_layer(layer);
+                let Some(layer) = layer else {
+                    continue;
+                };
+
+                shader.light_pos.set(light.pos());
+                shader.far_plane.set(light.far_plane());
+                shader
+                    .face_matrices
+                    .set(cube_face_matrices(light.pos(), light.far_plane()));
+                shader.layer.set(layer as i32);
+                draw(shader);
+            }
+        });
+        unsafe {
+            self.gl
+                .BindTextureUnit(POINT_SHADOW_UNIT, self.map.texture())
+        };
+    }
+}
+
+/// View projection matrices looking out of each face of a cube centered on `pos`, in the
+/// +X, -X, +Y, -Y, +Z, -Z order of cube map layers.
+pub fn cube_face_matrices(pos: Vec3, far_plane: f32) -> [Mat4; 6] {
+    let projection = Mat4::perspective_rh_gl(90.0_f32.to_radians(), 1.0, NEAR_PLANE, far_plane);
+    // Cube map faces are laid out as if seen from inside the cube, hence the flipped ups.
+    [
+        (Vec3::X, Vec3::NEG_Y),
+        (Vec3::NEG_X, Vec3::NEG_Y),
+        (Vec3::Y, Vec3::Z),
+        (Vec3::NEG_Y, Vec3::NEG_Z),
+        (Vec3::Z, Vec3::NEG_Y),
+        (Vec3::NEG_Z, Vec3::NEG_Y),
+    ]
+    .map(|(dir, up)| projection * Mat4::look_to_rh(pos, dir, up))
+}
@@ -0,0 +1,81 @@
+use std::rc::Rc;
+
+use crate::{
+    gl::Gl,
+    light::{Light, SpotLights},
+    renderer::framebuffer::FramebufferError,
+    shader::{DepthShader, UniformGetSet},
+};
+
+use super::{ShadowAtlas, ShadowMap, ShadowMapKind, SPOT_SHADOW_UNIT};
+
+/// Shadow maps of the spot lights casting shadows, each in its own tile of one atlas texture.
+pub struct SpotShadows {
+    gl: Gl,
+    map: ShadowMap,
+    atlas: ShadowAtlas,
+    /// Width of the tile each light gets.
+    tile_resolution: u32,
+    depth_shader: Rc<DepthShader>,
+}
+
+impl SpotShadows {
+    pub fn new(
+        gl: &Gl,
+        atlas_resolution: u32,
+        tile_resolution: u32,
+        depth_shader: Rc<DepthShader>,
+    ) -> Result<Self, FramebufferError> {
+        let atlas = ShadowAtlas::new(atlas_resolution);
+        Ok(Self {
+            gl: gl.clone(),
+            map: ShadowMap::new(gl, ShadowMapKind::Flat, atlas.size())?,
+            atlas,
+            tile_resolution,
+            depth_shader,
+        })
+    }
+
+    pub fn tile_resolution(&self) -> u32 {
+        self.tile_resolution
+    }
+    /// Lights that don't fit in the atlas anymore cast no shadows.
+    pub fn set_tile_resolution(&mut self, tile_resolution: u32) {
+        self.tile_resolution = tile_resolution;
+    }
+
+    /// Renders the shadows of the enabled lights that cast them and tells each light where in
+    /// the atlas its shadow is, so call it after moving the lights and before uploading them.
+    /// `draw` draws every shadow caster with the shader it's given.
+    pub fn render(&mut self, lights: &mut SpotLights, draw: impl Fn(&DepthShader)) {
+        let gl = &self.gl;
+        let atlas = &mut self.atlas;
+        let shader = &self.depth_shader;
+        atlas.clear();
+        self.map.render(|| {
+            for light in lights.iter_mut() {
+                let tile = if light.enabled() && light.casts_shadow() {
+                    atlas.allocate(self.tile_resolution)
+                } else {
+                    None
+                };
+                light.set_shadow_rect(tile.map(|tile| tile.uv_rect(atlas.size())));
+                let Some(tile) = tile else {
+                    continue;
+                };
+
+                unsafe {
+                    gl.Viewport(
+                        tile.offset.x as i32,
+                        tile.offset.y as i32,
+                        tile.size as i32,
+                        tile.size as i32,
+                    );
+                }
+                shader.light_space.set(light.shadow_matrix());
+                draw(shader);
+            }
+        });
+        unsafe { gl.BindTextureUnit(SPOT_SHADOW_UNIT, self.map.texture()) };
+    }
+}
@@ -6,7 +6,7 @@ use glutin::{
     context::PossiblyCurrentContext,
     display::GetGlDisplay,
     prelude::{GlDisplay, NotCurrentGlContext, PossiblyCurrentGlContext},
-    surface::{GlSurface, Surface, SwapInterval, WindowSurface},
+    surface::{GlSurface, Surface, SurfaceAttributesBuilder, SwapInterval, WindowSurface},
 };
 use glutin_winit::{DisplayBuilder, GlWindow};
 use winit::{
@@ -34,7 +34,7 @@ impl App {
     pub fn new(template: ConfigTemplateBuilder, display_builder: DisplayBuilder) -> Self {
         App {
             template,
-            gl_display: GlDisplayCreationState::Builder(display_builder),
+            gl_display: GlDisplayCreationState::Builder(Box::new(display_builder)),
             window: None,
             surface: None,
             gl_context: None,
@@ -45,15 +45,11 @@ impl App {
     }
 }
 
+/// Picks an sRGB capable config with the fewest samples. The renderer multisamples the scene
+/// offscreen and gamma encodes its output, which needs an sRGB window to happen on write.
 fn config_picker(configs: Box<dyn Iterator<Item = Config> + '_>) -> Config {
     configs
-        .reduce(|accum, config| {
-            if config.num_samples() > accum.num_samples() {
-                config
-            } else {
-                accum
-            }
-        })
+        .min_by_key(|config| (!config.srgb_capable(), config.num_samples()))
         .unwrap()
 }
 impl ApplicationHandler for App {
@@ -95,7 +91,7 @@ impl ApplicationHandler for App {
         window.set_cursor_grab(CursorGrabMode::Locked).unwrap();
 
         let attrs = window
-            .build_surface_attributes(Default::default())
+            .build_surface_attributes(SurfaceAttributesBuilder::new().with_srgb(Some(true)))
             .expect("Failed to build surface attributes");
         let gl_surface = unsafe {
             gl_config
@@ -161,7 +157,7 @@ impl ApplicationHandler for App {
                         NonZeroU32::new(size.height).unwrap(),
                     );
 
-                    let renderer = self.renderer.as_ref().unwrap();
+                    let renderer = self.renderer.as_mut().unwrap();
                     renderer.resize(size.width as i32, size.height as i32);
                 }
             }
@@ -171,6 +167,12 @@ impl ApplicationHandler for App {
                 is_synthetic: _,
             } => match event.state {
                 ElementState::Pressed => {
+                    if let (PhysicalKey::Code(key), false, Some(renderer)) =
+                        (event.physical_key, event.repeat, self.renderer.as_mut())
+                    {
+                        renderer.handle_light_key(key);
+                        renderer.handle_post_process_key(key);
+                    }
                     self.keys_down.insert(event.physical_key);
                 }
                 ElementState::Released => {
@@ -233,8 +235,9 @@ fn group_keys(keys_down: &HashSet<PhysicalKey>) -> GroupedKeys {
 }
 
 enum GlDisplayCreationState {
-    /// The display was not built yet.
-    Builder(DisplayBuilder),
+    /// The display was not built yet. Boxed since the builder is far larger than `Init`, which
+    /// `clippy::large_enum_variant` rejects.
+    Builder(Box<DisplayBuilder>),
     /// The display was already created for the application.
     Init,
 }